@@ -0,0 +1,75 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[path = "build/midi.rs"]
+mod midi;
+#[path = "build/replay.rs"]
+mod replay;
+#[path = "build/songc.rs"]
+mod songc;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build/midi.rs");
+    println!("cargo:rerun-if-changed=build/replay.rs");
+    println!("cargo:rerun-if-changed=build/songc.rs");
+    println!("cargo:rerun-if-env-changed=SPACE_DELIVERY_REPLAY");
+    println!("cargo:rerun-if-changed=music");
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+
+    // A replay to play instead of reading the gamepad, empty for a normal build.
+    let replay = env::var_os("SPACE_DELIVERY_REPLAY").map_or_else(Vec::new, |path| {
+        println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+        let contents = fs::read(&path).expect("readable SPACE_DELIVERY_REPLAY");
+        let replay = replay::read(&contents)
+            .unwrap_or_else(|error| panic!("{}: {error}", path.to_string_lossy()));
+        // A reminder that this build does not read the gamepad at first.
+        match replay.end {
+            Some((frames, checksum)) => println!(
+                "cargo:warning=playing a {frames} frame replay, it should end with checksum {checksum:08x}"
+            ),
+            None => println!("cargo:warning=playing a replay before taking input"),
+        }
+        replay.data
+    });
+    fs::write(Path::new(&out_dir).join("replay.sdr"), replay).expect("writable OUT_DIR");
+
+    let mut songs: Vec<_> = fs::read_dir("music")
+        .expect("music directory")
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "song" || extension == "mid")
+        })
+        .collect();
+    songs.sort();
+
+    for path in songs {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let stem = path.file_stem().expect("song file name");
+        let out = Path::new(&out_dir).join(stem);
+        let source = if path.extension().is_some_and(|extension| extension == "mid") {
+            assert!(
+                !path.with_extension("song").exists(),
+                "{} and its .song would both compile to {}.sds",
+                path.display(),
+                stem.to_string_lossy()
+            );
+            let data = fs::read(&path).expect("readable MIDI file");
+            let conversion =
+                midi::convert(&data).unwrap_or_else(|error| panic!("{}: {error}", path.display()));
+            for warning in &conversion.warnings {
+                println!("cargo:warning={}: {warning}", path.display());
+            }
+            // Kept next to the binary as a starting point for hand editing.
+            fs::write(out.with_extension("song"), &conversion.source).expect("writable OUT_DIR");
+            conversion.source
+        } else {
+            fs::read_to_string(&path).expect("readable song")
+        };
+        let song = songc::compile(&source)
+            .unwrap_or_else(|error| panic!("{}:{}: {}", path.display(), error.line, error.message));
+        fs::write(out.with_extension("sds"), song).expect("writable OUT_DIR");
+    }
+}
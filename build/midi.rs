@@ -0,0 +1,485 @@
+// Converts a Standard MIDI File into the text song notation read by `songc.rs`.
+//
+// Tracks named after a channel ("pulse1", "pulse2", "triangle" or "noise", in any case) play
+// on it. Notes on MIDI channel 10 go to noise, and the remaining tracks fill whichever of
+// pulse1, pulse2 and triangle are left, in file order. Channels play one note at a time:
+// of notes starting together the highest is kept, and a note cuts off the one before it.
+// Times are rounded to the game's ticks and cut into one pattern per bar, so notes held
+// over a bar line are played again on the next bar. Velocities are ignored, the volume
+// comes from the instrument.
+
+use crate::songc::{SEQUENCE_LENGTH, TICKS_PER_QUARTER};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+const CHANNELS: [&str; 4] = ["pulse1", "pulse2", "triangle", "noise"];
+const NOISE: usize = 3;
+const DRUMS: u8 = 9;
+const NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+const MAX_PATTERNS: usize = 64;
+const MAX_ROWS: usize = 255;
+const MAX_TICKS: u32 = 255;
+
+pub struct Conversion {
+    pub source: String,
+    pub warnings: Vec<String>,
+}
+
+struct RawNote {
+    start: u32,
+    end: u32,
+    key: u8,
+    channel: u8,
+}
+
+struct Track {
+    name: String,
+    notes: Vec<RawNote>,
+}
+
+// A note in game ticks.
+#[derive(Clone, Copy)]
+struct Note {
+    start: u32,
+    end: u32,
+    key: u8,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.data.len() {
+            return Err("file ends in the middle of a chunk".to_owned());
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // Variable-length quantity, seven bits per byte with the high bit meaning "more".
+    fn varint(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = value << 7 | u32::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("variable-length number longer than 4 bytes".to_owned())
+    }
+}
+
+pub fn convert(data: &[u8]) -> Result<Conversion, String> {
+    let mut warnings = Vec::new();
+    let mut reader = Reader { data };
+    if reader.take(4)? != b"MThd" {
+        return Err("not a Standard MIDI File".to_owned());
+    }
+    let header_len = reader.u32()?;
+    let _format = reader.u16()?;
+    let track_count = reader.u16()?;
+    let division = reader.u16()?;
+    reader.take(header_len.saturating_sub(6) as usize)?;
+    if division & 0x8000 != 0 {
+        return Err(
+            "SMPTE time division is not supported, save with ticks per quarter note".to_owned(),
+        );
+    }
+    let division = u32::from(division.max(1));
+
+    let mut tempo = None;
+    let mut bar = None;
+    let mut tracks = Vec::new();
+    for _ in 0..track_count {
+        let id = reader.take(4)?;
+        let len = reader.u32()? as usize;
+        let chunk = reader.take(len)?;
+        if id == b"MTrk" {
+            tracks.push(parse_track(chunk, &mut tempo, &mut bar, &mut warnings)?);
+        }
+    }
+
+    // Microseconds per quarter note, 120 bpm when the file does not say.
+    let bpm = (60_000_000 + tempo.unwrap_or(500_000) / 2) / tempo.unwrap_or(500_000).max(1);
+    let bpm = if (20..=999).contains(&bpm) {
+        bpm
+    } else {
+        warnings.push(format!("tempo of {bpm} bpm clamped to 20..=999"));
+        bpm.clamp(20, 999)
+    };
+    let bar = bar.unwrap_or(TICKS_PER_QUARTER * 4).max(1);
+
+    let channels = assign(tracks, &mut warnings);
+    let quantise = |time: u32| {
+        let ticks = (u64::from(time) * u64::from(TICKS_PER_QUARTER) * 2 + u64::from(division))
+            / (u64::from(division) * 2);
+        u32::try_from(ticks).unwrap_or(u32::MAX)
+    };
+    let channels: Vec<Vec<Note>> = channels
+        .into_iter()
+        .zip(CHANNELS)
+        .map(|(notes, name)| monophonic(notes, name, quantise, &mut warnings))
+        .collect();
+
+    let end = channels
+        .iter()
+        .filter_map(|notes| notes.last())
+        .map(|note| note.end)
+        .max()
+        .unwrap_or(0);
+    let bars = end.div_ceil(bar) as usize;
+    if bars == 0 {
+        return Err("no notes to convert".to_owned());
+    }
+
+    let mut patterns: Vec<String> = Vec::new();
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut rows = Vec::new();
+    'bars: for index in 0..bars {
+        let start = index as u32 * bar;
+        let mut row = Vec::new();
+        let mut new_patterns = Vec::new();
+        for (notes, channel) in channels.iter().zip(CHANNELS) {
+            let Some(body) = pattern(notes, start, start + bar, channel, index, &mut warnings)
+            else {
+                row.push("-".to_owned());
+                continue;
+            };
+            let name = match names.get(&body) {
+                Some(name) => name.clone(),
+                None => {
+                    let name = format!("{channel}_{}", patterns.len() + new_patterns.len());
+                    new_patterns.push((name.clone(), body));
+                    name
+                }
+            };
+            row.push(name);
+        }
+        if patterns.len() + new_patterns.len() > MAX_PATTERNS || rows.len() == MAX_ROWS {
+            warnings.push(format!(
+                "song cut off at bar {}, songs hold {MAX_PATTERNS} different patterns and {MAX_ROWS} bars",
+                index + 1
+            ));
+            break 'bars;
+        }
+        for (name, body) in new_patterns {
+            names.insert(body.clone(), name.clone());
+            patterns.push(format!("pattern {name}\n  {body}\n"));
+        }
+        rows.push(row);
+    }
+
+    let mut source = String::new();
+    let _ = writeln!(source, "# Converted from MIDI by build/midi.rs.");
+    let _ = writeln!(source, "tempo {bpm}");
+    source.push_str(concat!(
+        "instrument pulse1 volume 25 duty 25 release 4\n",
+        "instrument pulse2 volume 20 duty 50 release 4\n",
+        "instrument triangle volume 40 release 2\n",
+        "instrument noise volume 8 release 2\n",
+    ));
+    for pattern in &patterns {
+        source.push('\n');
+        source.push_str(pattern);
+    }
+    source.push_str("\norder\n");
+    for row in &rows {
+        let _ = writeln!(source, "  {}", row.join(" "));
+    }
+    // Tempo and time signature changes are reported once, not once per change.
+    let mut seen = HashSet::new();
+    warnings.retain(|warning| seen.insert(warning.clone()));
+    Ok(Conversion { source, warnings })
+}
+
+fn parse_track(
+    data: &[u8],
+    tempo: &mut Option<u32>,
+    bar: &mut Option<u32>,
+    warnings: &mut Vec<String>,
+) -> Result<Track, String> {
+    let mut reader = Reader { data };
+    let mut name = String::new();
+    let mut notes = Vec::new();
+    let mut held: HashMap<(u8, u8), u32> = HashMap::new();
+    let mut time = 0u32;
+    let mut status = 0u8;
+    while !reader.data.is_empty() {
+        time = time.saturating_add(reader.varint()?);
+        let first = reader.byte()?;
+        match first {
+            0xff => {
+                let kind = reader.byte()?;
+                let len = reader.varint()? as usize;
+                let data = reader.take(len)?;
+                match (kind, data) {
+                    (0x03, _) => name = String::from_utf8_lossy(data).into_owned(),
+                    (0x51, &[a, b, c]) => {
+                        let microseconds = u32::from_be_bytes([0, a, b, c]);
+                        if tempo.is_some_and(|tempo| tempo != microseconds) {
+                            warnings.push(
+                                "tempo changes are ignored, the first tempo is used".to_owned(),
+                            );
+                        } else {
+                            *tempo = Some(microseconds);
+                        }
+                    }
+                    (0x58, &[numerator, denominator, ..]) => {
+                        let ticks =
+                            (TICKS_PER_QUARTER * 4 * u32::from(numerator)) >> denominator.min(31);
+                        if bar.is_some_and(|bar| bar != ticks) {
+                            warnings.push(
+                                "time signature changes are ignored, the first one is used"
+                                    .to_owned(),
+                            );
+                        } else if ticks > 0 {
+                            *bar = Some(ticks);
+                        }
+                    }
+                    (0x2f, _) => break,
+                    _ => {}
+                }
+                continue;
+            }
+            0xf0 | 0xf7 => {
+                let len = reader.varint()? as usize;
+                reader.take(len)?;
+                continue;
+            }
+            _ => {}
+        }
+        // Running status: a data byte reuses the last status byte.
+        let data1 = if first & 0x80 == 0 {
+            if status == 0 {
+                return Err("running status before any status byte".to_owned());
+            }
+            first
+        } else {
+            status = first;
+            reader.byte()?
+        };
+        let channel = status & 0x0f;
+        match status & 0xf0 {
+            0x80 | 0x90 => {
+                let velocity = reader.byte()?;
+                let key = (channel, data1);
+                if let Some(start) = held.remove(&key) {
+                    notes.push(RawNote {
+                        start,
+                        end: time,
+                        key: data1,
+                        channel,
+                    });
+                }
+                if status & 0xf0 == 0x90 && velocity > 0 {
+                    held.insert(key, time);
+                }
+            }
+            0xc0 | 0xd0 => {}
+            _ => {
+                reader.byte()?;
+            }
+        }
+    }
+    // Notes still held when the track ends stop there.
+    for ((channel, key), start) in held {
+        notes.push(RawNote {
+            start,
+            end: time,
+            key,
+            channel,
+        });
+    }
+    Ok(Track { name, notes })
+}
+
+fn assign(tracks: Vec<Track>, warnings: &mut Vec<String>) -> [Vec<RawNote>; 4] {
+    let mut channels: [Vec<RawNote>; 4] = Default::default();
+    let mut unnamed = Vec::new();
+    for track in tracks {
+        let lower = track.name.to_lowercase();
+        if let Some(index) = CHANNELS.iter().position(|channel| lower.contains(channel)) {
+            channels[index].extend(track.notes);
+            continue;
+        }
+        let (drums, notes): (Vec<_>, Vec<_>) = track
+            .notes
+            .into_iter()
+            .partition(|note| note.channel == DRUMS);
+        channels[NOISE].extend(drums);
+        if !notes.is_empty() {
+            unnamed.push((track.name, notes));
+        }
+    }
+    let mut free = (0..NOISE)
+        .filter(|&index| channels[index].is_empty())
+        .collect::<Vec<_>>()
+        .into_iter();
+    for (name, notes) in unnamed {
+        if let Some(index) = free.next() {
+            channels[index] = notes;
+        } else {
+            let name = if name.is_empty() {
+                "(unnamed)".to_owned()
+            } else {
+                name
+            };
+            warnings.push(format!(
+                "track `{name}` dropped with {} notes, every channel is taken",
+                notes.len()
+            ));
+        }
+    }
+    channels
+}
+
+fn monophonic(
+    mut notes: Vec<RawNote>,
+    channel: &str,
+    quantise: impl Fn(u32) -> u32,
+    warnings: &mut Vec<String>,
+) -> Vec<Note> {
+    notes.sort_by_key(|note| (note.start, std::cmp::Reverse(note.key)));
+    let (mut chords, mut cut, mut short, mut low) = (0, 0, 0, 0);
+    let mut out: Vec<Note> = Vec::new();
+    for note in notes {
+        // Key 0 is a rest in the song format.
+        if note.key == 0 {
+            low += 1;
+            continue;
+        }
+        let start = quantise(note.start);
+        let mut end = quantise(note.end);
+        if end <= start {
+            end = start + 1;
+            short += 1;
+        }
+        if let Some(last) = out.last_mut() {
+            if last.start == start {
+                chords += 1;
+                continue;
+            }
+            if last.end > start {
+                last.end = start;
+                cut += 1;
+            }
+        }
+        out.push(Note {
+            start,
+            end,
+            key: note.key,
+        });
+    }
+    let mut report = |count: u32, what: &str| {
+        if count > 0 {
+            warnings.push(format!("{channel}: {count} {what}"));
+        }
+    };
+    report(
+        chords,
+        "notes dropped from chords, channels play one note at a time",
+    );
+    report(cut, "notes cut short by the next note");
+    report(short, "notes lengthened to one tick");
+    report(low, "notes dropped for being below C#-1");
+    out
+}
+
+// One bar of one channel as pattern text, `None` when it is silent.
+fn pattern(
+    notes: &[Note],
+    start: u32,
+    end: u32,
+    channel: &str,
+    bar: usize,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let mut items: Vec<(Option<u8>, u32)> = Vec::new();
+    let mut cursor = start;
+    for note in notes
+        .iter()
+        .filter(|note| note.start < end && note.end > start)
+    {
+        let from = note.start.max(start);
+        let to = note.end.min(end);
+        if from > cursor {
+            push(&mut items, None, from - cursor);
+        }
+        push(&mut items, Some(note.key), to - from);
+        cursor = to;
+    }
+    if items.is_empty() {
+        return None;
+    }
+    if cursor < end {
+        push(&mut items, None, end - cursor);
+    }
+    if items.len() > SEQUENCE_LENGTH {
+        // The rest in place of the dropped notes may take more than one item, which leaves
+        // room for fewer notes.
+        let mut keep = SEQUENCE_LENGTH;
+        let rest = loop {
+            keep -= 1;
+            let rest: u32 = items[keep..].iter().map(|(_, ticks)| ticks).sum();
+            if keep + rest_items(rest) <= SEQUENCE_LENGTH {
+                break rest;
+            }
+        };
+        let dropped = items[keep..]
+            .iter()
+            .filter(|(key, _)| key.is_some())
+            .count();
+        items.truncate(keep);
+        push(&mut items, None, rest);
+        warnings.push(format!(
+            "{channel}: {dropped} notes dropped from bar {}, patterns hold {SEQUENCE_LENGTH} notes",
+            bar + 1
+        ));
+    }
+    let words: Vec<String> = items
+        .iter()
+        .map(|&(key, ticks)| match key {
+            Some(key) => format!(
+                "{}{}/t{ticks}",
+                NAMES[usize::from(key % 12)],
+                i32::from(key / 12) - 1
+            ),
+            None => format!("-/t{ticks}"),
+        })
+        .collect();
+    Some(words.join(" "))
+}
+
+// How many items `push` makes of a rest this long.
+const fn rest_items(ticks: u32) -> usize {
+    ticks.div_ceil(MAX_TICKS) as usize
+}
+
+// Splits anything longer than a note can be.
+fn push(items: &mut Vec<(Option<u8>, u32)>, key: Option<u8>, mut ticks: u32) {
+    while ticks > 0 {
+        let length = ticks.min(MAX_TICKS);
+        items.push((key, length));
+        ticks -= length;
+    }
+}
@@ -0,0 +1,61 @@
+// Reads a replay either as the raw bytes or as the text the game traces on a right click:
+//
+//     replay: 5344525001000000000000000017001000...
+//     replay end: 0000053ab8c1f2d4
+//
+// Lines without the `replay: ` prefix are ignored, so a whole console log can be passed in.
+
+pub struct Replay {
+    pub data: Vec<u8>,
+    /// Frame count and world checksum from the `replay end: ` line, when there is one.
+    pub end: Option<(u32, u32)>,
+}
+
+const MAGIC: &[u8] = b"SDRP";
+
+pub fn read(contents: &[u8]) -> Result<Replay, String> {
+    if contents.starts_with(MAGIC) {
+        return Ok(Replay {
+            data: contents.to_vec(),
+            end: None,
+        });
+    }
+    let text = std::str::from_utf8(contents).map_err(|_| "not a replay".to_owned())?;
+    let mut data = Vec::new();
+    let mut end = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(hex) = line.strip_prefix("replay end: ") {
+            let bytes = decode_hex(hex)?;
+            let [a, b, c, d, e, f, g, h] = bytes[..] else {
+                return Err(format!("`{line}` should hold 8 bytes"));
+            };
+            end = Some((
+                u32::from_be_bytes([a, b, c, d]),
+                u32::from_be_bytes([e, f, g, h]),
+            ));
+        } else if let Some(hex) = line.strip_prefix("replay: ") {
+            // Status lines like "replay: finished" are words, not hex.
+            if hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                data.extend(decode_hex(hex)?);
+            }
+        }
+    }
+    if !data.starts_with(MAGIC) {
+        return Err("no replay lines found".to_owned());
+    }
+    Ok(Replay { data, end })
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits in `{hex}`"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| {
+            u8::from_str_radix(&hex[index..index + 2], 16)
+                .map_err(|_| format!("`{hex}` is not hex"))
+        })
+        .collect()
+}
@@ -0,0 +1,363 @@
+// Compiles the text song notation into the binary format read by `src/song.rs`.
+//
+//     # comment
+//     tempo 120                        beats per minute
+//     instrument pulse1 volume 30 peak 60 duty 50 attack 2 decay 4 release 6 vibrato 2 8
+//     instrument pulse2 arpeggio 4 7 3  semitones above the note, then frames per step
+//     pattern lead
+//       D#4/4 F#4 -/2 A#4/8            note, octave and length; lengths repeat until changed
+//       D#4/8. C4/8t D4 E4             dotted and triplet lengths
+//       D#4/4@40 C4/1>G4               @ sets the note's volume, > slides to another note
+//     order
+//       lead  -  bass  hats            pulse1 pulse2 triangle noise, "-" is silent
+//
+// Lengths are 1 (whole), 2, 4, 8 or 16, optionally dotted (`.`) or triplet (`t`), or a raw
+// tick count like `/t5` with 12 ticks to the quarter note.
+// Duty is one of 12, 25, 50 or 75 percent, volumes go to 100 and envelope times are frames. Vibrato takes a depth in percent and frames per swing.
+
+pub const VERSION: u8 = 3;
+pub const TICKS_PER_QUARTER: u32 = 12;
+pub const SEQUENCE_LENGTH: usize = 16;
+const CHANNELS: [&str; 4] = ["pulse1", "pulse2", "triangle", "noise"];
+const SILENT: u8 = 0xff;
+const INSTRUMENT_VOLUME: u8 = 0xff;
+
+pub struct Error {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Instrument {
+    volume: u8,
+    peak: u8,
+    duty: u8,
+    attack: u8,
+    decay: u8,
+    release: u8,
+    effect: [u8; 4],
+}
+
+pub struct Note {
+    pub pitch: u8,
+    pub ticks: u8,
+    pub volume: u8,
+    pub slide: u8,
+}
+
+struct Pattern {
+    name: String,
+    notes: Vec<Note>,
+}
+
+enum Section {
+    Header,
+    Pattern,
+    Order,
+}
+
+pub fn compile(source: &str) -> Result<Vec<u8>, Error> {
+    let mut tempo: u16 = 257;
+    let mut instruments = [Instrument {
+        volume: 25,
+        duty: 1,
+        release: 4,
+        ..Instrument::default()
+    }; 4];
+    let mut patterns: Vec<Pattern> = Vec::new();
+    let mut rows: Vec<[u8; 4]> = Vec::new();
+    let mut section = Section::Header;
+    let mut ticks = 6;
+
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let fail = |message: String| Error {
+            line: number,
+            message,
+        };
+        let line = strip_comment(line);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some(&keyword) = words.first() else {
+            continue;
+        };
+        match keyword {
+            "tempo" => {
+                let word = words
+                    .get(1)
+                    .ok_or_else(|| fail("`tempo` needs a value".to_owned()))?;
+                tempo = word
+                    .parse()
+                    .ok()
+                    .filter(|bpm| (20..=999).contains(bpm))
+                    .ok_or_else(|| {
+                        fail(format!("tempo is 20 to 999 beats per minute, not `{word}`"))
+                    })?;
+            }
+            "instrument" => {
+                let channel = channel_index(words.get(1).copied()).map_err(fail)?;
+                let settings = words.get(2..).unwrap_or_default();
+                parse_instrument(settings, &mut instruments[channel]).map_err(fail)?;
+            }
+            "pattern" => {
+                let name = words
+                    .get(1)
+                    .ok_or_else(|| fail("pattern needs a name".to_owned()))?;
+                if *name == "-" || patterns.iter().any(|pattern| pattern.name == *name) {
+                    return Err(fail(format!("pattern name `{name}` is taken")));
+                }
+                patterns.push(Pattern {
+                    name: (*name).to_owned(),
+                    notes: Vec::new(),
+                });
+                section = Section::Pattern;
+            }
+            "order" => section = Section::Order,
+            _ => match section {
+                Section::Header => return Err(fail(format!("unknown keyword `{keyword}`"))),
+                Section::Pattern => {
+                    let pattern = patterns
+                        .last_mut()
+                        .expect("pattern section without a pattern");
+                    for word in &words {
+                        let note = parse_note(word, ticks).map_err(fail)?;
+                        ticks = note.ticks;
+                        pattern.notes.push(note);
+                    }
+                    if pattern.notes.len() > SEQUENCE_LENGTH {
+                        return Err(fail(format!(
+                            "pattern `{}` has more than {SEQUENCE_LENGTH} notes",
+                            pattern.name
+                        )));
+                    }
+                }
+                Section::Order => {
+                    if words.len() != CHANNELS.len() {
+                        return Err(fail(format!(
+                            "order rows need {} columns: {}",
+                            CHANNELS.len(),
+                            CHANNELS.join(" ")
+                        )));
+                    }
+                    let mut row = [SILENT; 4];
+                    for (slot, word) in row.iter_mut().zip(&words) {
+                        if *word != "-" {
+                            let index =
+                                patterns
+                                    .iter()
+                                    .position(|pattern| pattern.name == *word)
+                                    .ok_or_else(|| fail(format!("no pattern called `{word}`")))?;
+                            *slot = u8::try_from(index)
+                                .map_err(|_| fail("too many patterns".to_owned()))?;
+                        }
+                    }
+                    rows.push(row);
+                }
+            },
+        }
+    }
+
+    if patterns.len() > 64 {
+        return Err(Error {
+            line: 0,
+            message: "a song can have at most 64 patterns".to_owned(),
+        });
+    }
+    if rows.len() > 255 {
+        return Err(Error {
+            line: 0,
+            message: "a song can have at most 255 order rows".to_owned(),
+        });
+    }
+
+    let mut out = b"SDSG".to_vec();
+    out.push(VERSION);
+    out.extend_from_slice(&tempo.to_le_bytes());
+    for instrument in &instruments {
+        out.extend_from_slice(&[
+            instrument.volume,
+            instrument.peak,
+            instrument.duty,
+            instrument.attack,
+            instrument.decay,
+            instrument.release,
+        ]);
+        out.extend_from_slice(&instrument.effect);
+    }
+    out.push(patterns.len() as u8);
+    for pattern in &patterns {
+        out.push(pattern.notes.len() as u8);
+        for note in &pattern.notes {
+            out.extend_from_slice(&[note.pitch, note.ticks, note.volume, note.slide]);
+        }
+    }
+    out.push(rows.len() as u8);
+    for row in &rows {
+        out.extend_from_slice(row);
+    }
+    Ok(out)
+}
+
+fn strip_comment(line: &str) -> &str {
+    // `#` starts a comment unless it is a sharp right after a note letter.
+    let bytes = line.as_bytes();
+    for (index, &byte) in bytes.iter().enumerate() {
+        if byte == b'#' {
+            let after_letter = index > 0 && matches!(bytes[index - 1], b'A'..=b'G');
+            if !after_letter {
+                return &line[..index];
+            }
+        }
+    }
+    line
+}
+
+fn channel_index(name: Option<&str>) -> Result<usize, String> {
+    let name = name.unwrap_or("");
+    CHANNELS
+        .iter()
+        .position(|channel| *channel == name)
+        .ok_or_else(|| {
+            format!(
+                "unknown channel `{name}`, expected one of {}",
+                CHANNELS.join(", ")
+            )
+        })
+}
+
+fn parse_instrument(words: &[&str], instrument: &mut Instrument) -> Result<(), String> {
+    let mut words = words.iter();
+    while let Some(&key) = words.next() {
+        let mut value = |min, max| {
+            let word = words
+                .next()
+                .ok_or_else(|| format!("`{key}` needs a value"))?;
+            parse_number(word, min, max)
+        };
+        match key {
+            "volume" => instrument.volume = value(0, 100)?,
+            "peak" => instrument.peak = value(0, 100)?,
+            "attack" => instrument.attack = value(0, 255)?,
+            "decay" => instrument.decay = value(0, 255)?,
+            "release" => instrument.release = value(0, 255)?,
+            "duty" => {
+                instrument.duty = match value(12, 75)? {
+                    12 => 0,
+                    25 => 1,
+                    50 => 2,
+                    75 => 3,
+                    duty => return Err(format!("duty must be 12, 25, 50 or 75, not {duty}")),
+                }
+            }
+            "vibrato" => {
+                let depth = value(1, 100)?;
+                instrument.effect = [1, depth, 0, value(1, 255)?];
+            }
+            "arpeggio" => {
+                let first = value(0, 48)?;
+                let second = value(0, 48)?;
+                instrument.effect = [2, first, second, value(1, 255)?];
+            }
+            _ => return Err(format!("unknown instrument setting `{key}`")),
+        }
+    }
+    Ok(())
+}
+
+fn parse_number(word: &str, min: u8, max: u8) -> Result<u8, String> {
+    word.parse::<u8>()
+        .ok()
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| format!("expected a number from {min} to {max}, got `{word}`"))
+}
+
+/// Parses `C#4/8`, `Eb3@40`, `-/4.` or `C4/1>G4`; the length carries over when left out.
+pub fn parse_note(word: &str, ticks: u8) -> Result<Note, String> {
+    let (rest, slide) = match word.split_once('>') {
+        Some((_, "-")) => return Err(format!("`{word}` slides to a rest")),
+        Some((rest, target)) => (rest, parse_pitch(target, word)?),
+        None => (word, 0),
+    };
+    let (rest, volume) = match rest.split_once('@') {
+        Some((rest, volume)) => (rest, parse_number(volume, 0, 100)?),
+        None => (rest, INSTRUMENT_VOLUME),
+    };
+    let (name, ticks) = match rest.split_once('/') {
+        Some((name, length)) => (name, parse_length(length, word)?),
+        None => (rest, ticks),
+    };
+    let pitch = if name == "-" {
+        0
+    } else {
+        parse_pitch(name, word)?
+    };
+    Ok(Note {
+        pitch,
+        ticks,
+        volume,
+        slide,
+    })
+}
+
+/// Turns `4`, `8.`, `8t` or `t5` into ticks.
+fn parse_length(length: &str, word: &str) -> Result<u8, String> {
+    let invalid = || format!("`{word}` has a bad length, lengths look like 4, 8., 8t or t5");
+    if let Some(ticks) = length.strip_prefix('t') {
+        return ticks
+            .parse::<u8>()
+            .ok()
+            .filter(|&ticks| ticks > 0)
+            .ok_or_else(invalid);
+    }
+    let (division, numerator, denominator) = if let Some(division) = length.strip_suffix('.') {
+        (division, 3, 2)
+    } else if let Some(division) = length.strip_suffix('t') {
+        (division, 2, 3)
+    } else {
+        (length, 1, 1)
+    };
+    let division: u32 = match division.parse() {
+        Ok(division @ (1 | 2 | 4 | 8 | 16)) => division,
+        _ => return Err(invalid()),
+    };
+    let whole = TICKS_PER_QUARTER * 4 * numerator;
+    if !whole.is_multiple_of(division * denominator) {
+        return Err(format!("`{word}` is shorter than a tick"));
+    }
+    u8::try_from(whole / (division * denominator)).map_err(|_| format!("`{word}` is too long"))
+}
+
+/// Turns `C#4` or `Eb3` into a MIDI note number.
+fn parse_pitch(name: &str, word: &str) -> Result<u8, String> {
+    let mut chars = name.chars();
+    let letter = chars
+        .next()
+        .ok_or_else(|| format!("empty note in `{word}`"))?;
+    let mut semitone: i32 = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return Err(format!("`{word}` is not a note, notes look like C#4/2")),
+    };
+    let rest = chars.as_str();
+    let octave = if let Some(octave) = rest.strip_prefix('#') {
+        semitone += 1;
+        octave
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        semitone -= 1;
+        octave
+    } else {
+        rest
+    };
+    let octave: i32 = octave
+        .parse()
+        .map_err(|_| format!("`{word}` has no octave, notes look like C#4/2"))?;
+    let midi = (octave + 1) * 12 + semitone;
+    match u8::try_from(midi) {
+        Ok(midi) if (1..=127).contains(&midi) => Ok(midi),
+        _ => Err(format!("`{word}` is out of range")),
+    }
+}
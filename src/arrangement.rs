@@ -1,8 +1,8 @@
+use crate::theory::{self, Pitch, Tempo, FRAMES_PER_MINUTE, TICKS_PER_QUARTER};
 use crate::wasm4;
-use crate::FRAMES_PER_TICK;
 use heapless::Vec;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Wave {
     Pulse1,
     Pulse2,
@@ -13,58 +13,182 @@ pub enum Wave {
 impl Wave {
     pub const fn flags(self) -> u32 {
         match self {
-            Wave::Pulse1 => wasm4::TONE_PULSE1 | wasm4::TONE_MODE2,
-            Wave::Pulse2 => wasm4::TONE_PULSE2 | wasm4::TONE_MODE2,
-            Wave::Triangle => wasm4::TONE_TRIANGLE,
-            Wave::Noise => wasm4::TONE_NOISE,
+            Self::Pulse1 => wasm4::TONE_PULSE1,
+            Self::Pulse2 => wasm4::TONE_PULSE2,
+            Self::Triangle => wasm4::TONE_TRIANGLE,
+            Self::Noise => wasm4::TONE_NOISE,
+        }
+    }
+}
+
+/// Pulse width of the square channels, ignored by triangle and noise.
+#[derive(Clone, Copy)]
+pub enum Duty {
+    Eighth,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Duty {
+    pub const fn flags(self) -> u32 {
+        match self {
+            Self::Eighth => wasm4::TONE_MODE1,
+            Self::Quarter => wasm4::TONE_MODE2,
+            Self::Half => wasm4::TONE_MODE3,
+            Self::ThreeQuarters => wasm4::TONE_MODE4,
         }
     }
 }
 
+/// Pitch modulation applied by `Channel` while a note sounds; speeds are in frames.
 #[derive(Clone, Copy)]
-enum NoteLength {
+pub enum Effect {
+    None,
+    /// Swings the pitch `depth` percent up and down, one swing per `speed` frames.
+    Vibrato {
+        depth: u8,
+        speed: u8,
+    },
+    /// Cycles the note, `first` and `second` semitones above it, `speed` frames each.
+    Arpeggio {
+        first: u8,
+        second: u8,
+        speed: u8,
+    },
+}
+
+/// Volume envelope and timbre of a channel; times are in frames, volumes are 0..=100.
+#[derive(Clone, Copy)]
+pub struct Instrument {
+    pub attack: u8,
+    pub decay: u8,
+    pub release: u8,
+    /// Sustain volume, notes can override it.
+    pub volume: u8,
+    /// Volume at the end of the attack, 0 means the same as the sustain volume.
+    pub peak: u8,
+    pub duty: Duty,
+    pub effect: Effect,
+}
+
+impl Instrument {
+    pub const DEFAULT: Self = Self {
+        attack: 0,
+        decay: 0,
+        release: 4,
+        volume: 25,
+        peak: 0,
+        duty: Duty::Quarter,
+        effect: Effect::None,
+    };
+
+    /// Packs the envelope for `wasm4::tone`, sustaining whatever is left of `frames`.
+    fn duration(self, frames: u8) -> u32 {
+        let sustain = frames
+            .saturating_sub(self.attack)
+            .saturating_sub(self.decay)
+            .saturating_sub(self.release);
+        u32::from(self.attack) << 24
+            | u32::from(self.decay) << 16
+            | u32::from(self.release) << 8
+            | u32::from(sustain)
+    }
+
+    /// Packs peak and sustain volume, both scaled by `gain` percent.
+    fn volume(self, volume: u8, gain: u8) -> u32 {
+        let peak = if self.peak == 0 { volume } else { self.peak };
+        let scale = |volume: u8| (u32::from(volume) * u32::from(gain)).div_euclid(100);
+        scale(peak) << 8 | scale(volume)
+    }
+}
+
+/// Lengths in ticks, `TICKS_PER_QUARTER` to the quarter note.
+#[derive(Clone, Copy)]
+pub enum NoteLength {
     Whole,
     Half,
     Quarter,
     QuarterDot,
     Eighth,
+    Ticks(u8),
 }
 
 impl NoteLength {
-    pub const fn value(self) -> u32 {
+    pub fn value(self) -> u32 {
         match self {
-            NoteLength::Whole => 8,
-            NoteLength::Half => 4,
-            NoteLength::QuarterDot => 3,
-            NoteLength::Quarter => 2,
-            NoteLength::Eighth => 1,
+            Self::Whole => TICKS_PER_QUARTER * 4,
+            Self::Half => TICKS_PER_QUARTER * 2,
+            Self::QuarterDot => (TICKS_PER_QUARTER * 3).div_euclid(2),
+            Self::Quarter => TICKS_PER_QUARTER,
+            Self::Eighth => TICKS_PER_QUARTER.div_euclid(2),
+            Self::Ticks(ticks) => u32::from(ticks),
         }
     }
+
+    /// Half as long again, e.g. a dotted eighth.
+    pub fn dotted(self) -> Self {
+        Self::from_ticks((self.value() * 3).div_euclid(2))
+    }
+
+    /// Two thirds as long, three of them fill the time of two.
+    pub fn triplet(self) -> Self {
+        Self::from_ticks((self.value() * 2).div_euclid(3))
+    }
+
+    fn from_ticks(ticks: u32) -> Self {
+        Self::Ticks(u8::try_from(ticks).unwrap_or(u8::MAX))
+    }
 }
 
 #[derive(Clone, Copy)]
-struct Frequency(Option<u16>);
+pub struct Frequency(pub Option<u16>);
+
+impl From<Pitch> for Frequency {
+    fn from(pitch: Pitch) -> Self {
+        Self(Some(pitch.frequency()))
+    }
+}
 
 #[derive(Clone, Copy)]
-struct Note {
+pub struct Note {
     frequency: Frequency,
     length: NoteLength,
+    volume: Option<u8>,
+    slide: Option<u16>,
 }
 
 impl Note {
     pub const fn new(frequency: Frequency, length: NoteLength) -> Self {
-        Self { frequency, length }
+        Self {
+            frequency,
+            length,
+            volume: None,
+            slide: None,
+        }
+    }
+
+    /// Plays this note at `volume` instead of the instrument's sustain volume.
+    pub const fn with_volume(mut self, volume: u8) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+
+    /// Glides from the note's frequency to `frequency` over its length; effects are skipped.
+    pub const fn with_slide(mut self, frequency: u16) -> Self {
+        self.slide = Some(frequency);
+        self
     }
 }
 
-const SEQUENCE_LENGTH: usize = 8;
+pub const SEQUENCE_LENGTH: usize = 16;
 #[derive(Clone)]
 pub struct Sequence {
     notes: Vec<Note, SEQUENCE_LENGTH>,
 }
 
 impl Sequence {
-    const fn new(notes: Vec<Note, SEQUENCE_LENGTH>) -> Self {
+    pub const fn new(notes: Vec<Note, SEQUENCE_LENGTH>) -> Self {
         Self { notes }
     }
 
@@ -76,7 +200,7 @@ impl Sequence {
 
     fn get_note(&self, time: u32) -> Option<Note> {
         if time == 0 {
-            return self.notes.get(0).copied();
+            return self.notes.first().copied();
         }
         let mut counter = 0;
         for note in &self.notes {
@@ -87,117 +211,69 @@ impl Sequence {
         }
         None
     }
-
-    pub fn gen_pattern(variant: u8, random_value: f32) -> Self {
-        let mut notes: Vec<Note, SEQUENCE_LENGTH> = Vec::new();
-        let sanity_check = match variant {
-            0 => {
-                if random_value < 0.1 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(Some(117)), NoteLength::Half),
-                        Note::new(Frequency(Some(139)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(156)), NoteLength::Half),
-                    ])
-                } else if random_value < 0.2 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(Some(139)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(185)), NoteLength::Half),
-                    ])
-                } else if random_value < 0.3 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(Some(185)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(208)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(156)), NoteLength::Half),
-                    ])
-                } else if random_value < 0.4 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(Some(139)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(117)), NoteLength::Half),
-                        Note::new(Frequency(Some(117)), NoteLength::Quarter),
-                        Note::new(Frequency(None), NoteLength::Quarter),
-                        Note::new(Frequency(Some(157)), NoteLength::Quarter),
-                    ])
-                } else if random_value < 0.5 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(None), NoteLength::Quarter),
-                        Note::new(Frequency(Some(156)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(139)), NoteLength::Half),
-                    ])
-                } else if random_value < 0.6 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(Some(208)), NoteLength::Eighth),
-                        Note::new(Frequency(Some(233)), NoteLength::Eighth),
-                        Note::new(Frequency(Some(277)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(311)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(370)), NoteLength::Eighth),
-                        Note::new(Frequency(Some(311)), NoteLength::Eighth),
-                        Note::new(Frequency(Some(233)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(208)), NoteLength::Eighth),
-                    ])
-                } else if random_value < 0.7 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(Some(208)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(156)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(185)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(139)), NoteLength::Half),
-                    ])
-                } else if random_value < 0.8 {
-                    notes.extend_from_slice(&[
-                        Note::new(Frequency(Some(117)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(139)), NoteLength::Half),
-                        Note::new(Frequency(Some(156)), NoteLength::Quarter),
-                        Note::new(Frequency(Some(185)), NoteLength::Half),
-                    ])
-                } else {
-                    notes.extend_from_slice(&[Note::new(Frequency(None), NoteLength::Quarter)])
-                }
-            }
-            10 => notes.extend_from_slice(&[Note::new(Frequency(Some(1000)), NoteLength::Quarter)]),
-            11 => notes.extend_from_slice(&[
-                Note::new(Frequency(Some(831)), NoteLength::Eighth),
-                Note::new(Frequency(Some(740)), NoteLength::Eighth),
-                Note::new(Frequency(Some(622)), NoteLength::Eighth),
-                Note::new(Frequency(Some(554)), NoteLength::Eighth),
-                Note::new(Frequency(Some(466)), NoteLength::Eighth),
-                Note::new(Frequency(Some(415)), NoteLength::Eighth),
-                Note::new(Frequency(Some(370)), NoteLength::Eighth),
-                Note::new(Frequency(Some(311)), NoteLength::Eighth),
-            ]),
-            _ => notes.extend_from_slice(&[Note::new(Frequency(None), NoteLength::Half)]),
-        };
-        sanity_check.expect("Tried to generate a sequence longer than SEQUENCE_LENGTH");
-        Self::new(notes)
-    }
 }
-// FREQUENCIES
-// 110 |-117-| 123 130 |-139-| 147 |-156-| 165 175 |-185-| 196 |-208-| 220
-// 220 |-233-| 247 262 |-277-| 294 |-311-| 330 349 |-370-| 392 |-415-| 440
-// 440 |-466-| 494 523 |-554-| 587 |-622-| 659 698 |-740-| 784 |-831-| 880
+// The note an effect is being applied to.
+#[derive(Clone, Copy)]
+struct Sounding {
+    frequency: u16,
+    volume: u8,
+    frame: u8,
+    frames: u8,
+}
+
 pub struct Channel {
     time: u32,
     wait_time: u32,
     pattern: Option<Sequence>,
-    instrument: Wave,
+    wave: Wave,
+    instrument: Instrument,
+    sounding: Option<Sounding>,
+    // Keeps time but stays silent while a sound effect has the channel.
+    muted: bool,
+    gain: u8,
 }
 
 impl Channel {
-    pub const fn new(instrument: Wave) -> Self {
+    pub const fn new(wave: Wave) -> Self {
         Self {
             time: 0,
             wait_time: 0,
             pattern: None,
-            instrument,
+            wave,
+            instrument: Instrument::DEFAULT,
+            sounding: None,
+            muted: false,
+            gain: 100,
         }
     }
 
+    pub const fn is_free(&self) -> bool {
+        self.pattern.is_none()
+    }
+
     pub fn try_set_pattern(&mut self, pattern: Sequence) {
         if self.pattern.is_none() {
             self.pattern = Some(pattern);
         }
     }
 
-    pub fn update(&mut self, delta_time: u32) {
-        if let Some(pattern) = &self.pattern {
+    /// Drops whatever was playing and starts `pattern` on the next tick.
+    pub fn set_pattern(&mut self, pattern: Sequence) {
+        self.pattern = Some(pattern);
+        self.time = 0;
+        self.wait_time = 0;
+        self.sounding = None;
+    }
+
+    pub const fn set_muted(&mut self, muted: bool) {
+        if muted {
+            self.sounding = None;
+        }
+        self.muted = muted;
+    }
+
+    pub fn update(&mut self, delta_time: u32, tempo: Tempo) {
+        if let Some(ref pattern) = self.pattern {
             self.time += delta_time;
             if self.wait_time > delta_time {
                 self.wait_time -= delta_time;
@@ -208,25 +284,111 @@ impl Channel {
             } else {
                 let note = pattern.get_note(self.time).expect("Note is None");
                 self.wait_time = note.length.value();
-                self.play_note(note.frequency, note.length);
+                self.play_note(note, tempo);
+            }
+        }
+    }
+
+    fn play_note(&mut self, note: Note, tempo: Tempo) {
+        let len = u8::try_from(tempo.frames(note.length.value())).unwrap_or(u8::MAX);
+        self.sounding = None;
+        if self.muted {
+            return;
+        }
+        if let Some(frequency) = note.frequency.0 {
+            let volume = note.volume.unwrap_or(self.instrument.volume);
+            tone(
+                frequency,
+                note.slide,
+                self.instrument.duration(len),
+                self.instrument.volume(volume, self.gain),
+                self.flags(),
+            );
+            if note.slide.is_none() {
+                self.sounding = Some(Sounding {
+                    frequency,
+                    volume,
+                    frame: 0,
+                    frames: len,
+                });
             }
         }
     }
 
-    fn play_note(&self, frequency: Frequency, length: NoteLength) {
-        let len: u8 = unsafe {
-            (u32::from(FRAMES_PER_TICK) * length.value())
-                .min(255)
-                .try_into()
-                .unwrap_unchecked()
+    /// Runs every frame; retriggers the sounding note with the instrument's effect applied.
+    fn apply_effect(&mut self) {
+        let Some(mut sounding) = self.sounding else {
+            return;
+        };
+        sounding.frame += 1;
+        if sounding.frame >= sounding.frames {
+            self.sounding = None;
+            return;
+        }
+        self.sounding = Some(sounding);
+
+        let instrument = self.instrument;
+        let left = sounding.frames - sounding.frame;
+        let (from, to, speed, start) = match instrument.effect {
+            Effect::None => return,
+            Effect::Vibrato { depth, speed } => {
+                let offset = u16::try_from(
+                    (u32::from(sounding.frequency) * u32::from(depth)).div_euclid(100),
+                )
+                .unwrap_or(0);
+                let low = sounding.frequency.saturating_sub(offset);
+                let high = sounding.frequency.saturating_add(offset);
+                // Let the attack and decay play out before wobbling.
+                let start = instrument.attack.saturating_add(instrument.decay);
+                let swing = sounding
+                    .frame
+                    .saturating_sub(start)
+                    .div_euclid(speed.max(1));
+                if swing % 2 == 0 {
+                    (low, Some(high), speed, start)
+                } else {
+                    (high, Some(low), speed, start)
+                }
+            }
+            Effect::Arpeggio {
+                first,
+                second,
+                speed,
+            } => {
+                let step = sounding.frame.div_euclid(speed.max(1)) % 3;
+                let semitones = [0, first, second].get(usize::from(step)).copied();
+                let semitones = i8::try_from(semitones.unwrap_or(0)).unwrap_or(0);
+                (
+                    theory::transpose_frequency(sounding.frequency, semitones),
+                    None,
+                    speed,
+                    0,
+                )
+            }
         };
-        if let Some(frequency) = frequency.0 {
-            tone(frequency, None, len / 2, Some(len / 2), 25, self.instrument);
+        let speed = speed.max(1);
+        if sounding.frame < start || (sounding.frame - start) % speed != 0 {
+            return;
         }
+        let sustain = speed.min(left);
+        let release = if left <= speed { instrument.release } else { 0 };
+        tone(
+            from,
+            to,
+            u32::from(release) << 8 | u32::from(sustain),
+            Instrument::DEFAULT.volume(sounding.volume, self.gain),
+            self.flags(),
+        );
+    }
+
+    const fn flags(&self) -> u32 {
+        self.wave.flags() | self.instrument.duty.flags()
     }
 }
 pub struct Arrangement {
-    time: u32,
+    // Fraction of a tick carried between frames, in 1/`FRAMES_PER_MINUTE`ths.
+    phase: u32,
+    tempo: Tempo,
     triangle: Option<Channel>,
     square1: Option<Channel>,
     square2: Option<Channel>,
@@ -241,7 +403,9 @@ impl Arrangement {
         noise: Option<Channel>,
     ) -> Self {
         Self {
-            time: 0,
+            // A full tick's worth, so the first frame starts playing straight away.
+            phase: FRAMES_PER_MINUTE,
+            tempo: Tempo::DEFAULT,
             triangle,
             square1,
             square2,
@@ -249,63 +413,113 @@ impl Arrangement {
         }
     }
 
-    pub fn try_add_pattern(&mut self, channel: Wave, pattern: Sequence) {
+    pub const fn set_tempo(&mut self, tempo: Tempo) {
+        self.tempo = tempo;
+    }
+
+    const fn channel(&self, channel: Wave) -> Option<&Channel> {
         match channel {
-            Wave::Triangle => {
-                if let Some(triangle) = &mut self.triangle {
-                    triangle.try_set_pattern(pattern);
-                }
-            }
-            Wave::Pulse1 => {
-                if let Some(square1) = &mut self.square1 {
-                    square1.try_set_pattern(pattern);
-                }
-            }
-            Wave::Pulse2 => {
-                if let Some(square2) = &mut self.square2 {
-                    square2.try_set_pattern(pattern);
-                }
-            }
-            Wave::Noise => {
-                if let Some(noise) = &mut self.noise {
-                    noise.try_set_pattern(pattern);
-                }
-            }
+            Wave::Triangle => self.triangle.as_ref(),
+            Wave::Pulse1 => self.square1.as_ref(),
+            Wave::Pulse2 => self.square2.as_ref(),
+            Wave::Noise => self.noise.as_ref(),
+        }
+    }
+
+    const fn channel_mut(&mut self, channel: Wave) -> Option<&mut Channel> {
+        match channel {
+            Wave::Triangle => self.triangle.as_mut(),
+            Wave::Pulse1 => self.square1.as_mut(),
+            Wave::Pulse2 => self.square2.as_mut(),
+            Wave::Noise => self.noise.as_mut(),
+        }
+    }
+
+    /// Whether `channel` exists and has nothing queued, so a new pattern would be accepted.
+    pub fn is_free(&self, channel: Wave) -> bool {
+        self.channel(channel).is_some_and(Channel::is_free)
+    }
+
+    /// Whether every channel has finished its pattern.
+    pub fn is_idle(&self) -> bool {
+        [&self.triangle, &self.square1, &self.square2, &self.noise]
+            .iter()
+            .all(|channel| channel.as_ref().is_none_or(Channel::is_free))
+    }
+
+    pub const fn set_instrument(&mut self, channel: Wave, instrument: Instrument) {
+        if let Some(channel) = self.channel_mut(channel) {
+            channel.instrument = instrument;
         }
     }
 
-    pub fn update(&mut self, delta_time: u32) {
-        self.time += delta_time;
-        if let Some(triangle) = &mut self.triangle {
-            triangle.update(delta_time);
+    /// Plays `pattern` on `channel` right away, cutting off what it was playing.
+    pub fn play_pattern(&mut self, channel: Wave, pattern: Sequence) {
+        if let Some(channel) = self.channel_mut(channel) {
+            channel.set_pattern(pattern);
         }
-        if let Some(square1) = &mut self.square1 {
-            square1.update(delta_time);
+    }
+
+    pub const fn set_muted(&mut self, channel: Wave, muted: bool) {
+        if let Some(channel) = self.channel_mut(channel) {
+            channel.set_muted(muted);
         }
-        if let Some(square2) = &mut self.square2 {
-            square2.update(delta_time);
+    }
+
+    /// Scales every channel's volume, in percent.
+    pub fn set_gain(&mut self, gain: u8) {
+        for channel in [
+            &mut self.triangle,
+            &mut self.square1,
+            &mut self.square2,
+            &mut self.noise,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            channel.gain = gain;
         }
-        if let Some(noise) = &mut self.noise {
-            noise.update(delta_time);
+    }
+
+    pub fn try_add_pattern(&mut self, channel: Wave, pattern: Sequence) {
+        if let Some(channel) = self.channel_mut(channel) {
+            channel.try_set_pattern(pattern);
+        }
+    }
+
+    /// Call once per frame: advances patterns every tick and effects every frame.
+    /// Fast tempos can run more than one tick in a frame.
+    pub fn update(&mut self) {
+        let mut ticks = 0;
+        while self.phase >= FRAMES_PER_MINUTE {
+            self.phase -= FRAMES_PER_MINUTE;
+            ticks += 1;
+        }
+        self.phase += self.tempo.tick_rate();
+        let tempo = self.tempo;
+        for channel in [
+            &mut self.triangle,
+            &mut self.square1,
+            &mut self.square2,
+            &mut self.noise,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            channel.apply_effect();
+            for _ in 0..ticks {
+                channel.update(1, tempo);
+            }
         }
     }
 }
 
-fn tone(
-    freq_first: u16,
-    freq_last: Option<u16>,
-    duration: u8,
-    release: Option<u8>,
-    volume: u32,
-    instrument: Wave,
-) {
+fn tone(freq_first: u16, freq_last: Option<u16>, duration: u32, volume: u32, flags: u32) {
     let freq_last = freq_last.unwrap_or(0);
-    let release = u32::from(release.unwrap_or(0));
-    let duration = u32::from(duration) | (release << 8);
     wasm4::tone(
         u32::from(freq_first) | (u32::from(freq_last) << 16),
         duration,
         volume,
-        instrument.flags(),
+        flags,
     );
 }
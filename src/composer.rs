@@ -0,0 +1,282 @@
+use crate::arrangement::{
+    Arrangement, Duty, Effect, Frequency, Instrument, Note, NoteLength, Sequence, Wave,
+    SEQUENCE_LENGTH,
+};
+use crate::theory::{pitch, Pitch, Tempo};
+use heapless::Vec;
+
+// The tonic the original hand-written phrases were built around.
+const ROOT: Pitch = pitch("D#3");
+// Nothing is written above this.
+const HIGHEST: Pitch = pitch("A5");
+
+const PENTATONIC: &[u8] = &[0, 3, 5, 7, 10];
+const PHRYGIAN: &[u8] = &[0, 1, 3, 5, 7, 8, 10];
+const DORIAN: &[u8] = &[0, 2, 3, 5, 7, 9, 10];
+const MAJOR: &[u8] = &[0, 2, 4, 5, 7, 9, 11];
+
+const BASS: Instrument = Instrument {
+    attack: 0,
+    decay: 2,
+    release: 4,
+    volume: 40,
+    peak: 60,
+    duty: Duty::Half,
+    effect: Effect::None,
+};
+
+/// What the player is doing right now, as far as the music is concerned.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mood {
+    Cruising,
+    GravityWell,
+    NearTarget,
+    Delivered,
+}
+
+impl Mood {
+    const fn scale(self) -> &'static [u8] {
+        match self {
+            Self::Cruising => PENTATONIC,
+            Self::GravityWell => PHRYGIAN,
+            Self::NearTarget => DORIAN,
+            Self::Delivered => MAJOR,
+        }
+    }
+
+    /// Chord roots in semitones above `ROOT`, one per bar.
+    const fn progression(self) -> [u8; 4] {
+        match self {
+            Self::Cruising => [0, 8, 5, 7],
+            Self::GravityWell => [0, 1, 0, 8],
+            Self::NearTarget => [0, 5, 10, 7],
+            Self::Delivered => [0, 5, 7, 0],
+        }
+    }
+
+    pub const fn tempo(self) -> Tempo {
+        match self {
+            Self::Cruising => Tempo::DEFAULT,
+            Self::GravityWell => Tempo::bpm(225),
+            Self::NearTarget => Tempo::bpm(300),
+            Self::Delivered => Tempo::bpm(360),
+        }
+    }
+
+    const fn lead(self) -> Instrument {
+        let instrument = Instrument {
+            attack: 2,
+            decay: 4,
+            release: 6,
+            volume: 22,
+            peak: 35,
+            duty: Duty::Quarter,
+            effect: Effect::Vibrato { depth: 1, speed: 8 },
+        };
+        match self {
+            Self::Cruising => instrument,
+            Self::GravityWell => Instrument {
+                attack: 6,
+                decay: 0,
+                release: 10,
+                duty: Duty::Half,
+                effect: Effect::Vibrato {
+                    depth: 2,
+                    speed: 12,
+                },
+                ..instrument
+            },
+            Self::NearTarget => Instrument {
+                attack: 0,
+                decay: 3,
+                release: 4,
+                duty: Duty::Eighth,
+                effect: Effect::None,
+                ..instrument
+            },
+            // Major triad arpeggio for the fanfare.
+            Self::Delivered => Instrument {
+                attack: 0,
+                decay: 2,
+                effect: Effect::Arpeggio {
+                    first: 4,
+                    second: 7,
+                    speed: 2,
+                },
+                ..instrument
+            },
+        }
+    }
+
+    /// 0 is sparse and calm, 3 is busy.
+    const fn intensity(self) -> u8 {
+        match self {
+            Self::Cruising => 1,
+            Self::GravityWell => 0,
+            Self::NearTarget => 2,
+            Self::Delivered => 3,
+        }
+    }
+}
+
+/// Writes one bar of melody on `Pulse1` and bass on `Triangle` whenever both are free.
+pub struct Composer {
+    mood: Mood,
+    bar: u8,
+    // Position of the last melody note in scale steps above the root.
+    degree: u8,
+}
+
+impl Composer {
+    pub const fn new() -> Self {
+        Self {
+            mood: Mood::Cruising,
+            bar: 0,
+            degree: 7,
+        }
+    }
+
+    pub fn set_mood(&mut self, mood: Mood) {
+        if self.mood != mood {
+            self.mood = mood;
+            self.bar = 0;
+        }
+    }
+
+    pub fn compose(&mut self, music: &mut Arrangement, random: &mut oorandom::Rand32) {
+        if !(music.is_free(Wave::Pulse1) && music.is_free(Wave::Triangle)) {
+            return;
+        }
+        let progression = self.mood.progression();
+        let chord = progression
+            .get(usize::from(self.bar) % progression.len())
+            .copied()
+            .unwrap_or(0);
+        music.set_tempo(self.mood.tempo());
+        music.set_instrument(Wave::Pulse1, self.mood.lead());
+        music.set_instrument(Wave::Triangle, BASS);
+        music.try_add_pattern(Wave::Pulse1, self.melody(chord, random));
+        music.try_add_pattern(Wave::Triangle, self.bass(chord));
+        self.bar = self.bar.wrapping_add(1);
+    }
+
+    fn melody(&mut self, chord: u8, random: &mut oorandom::Rand32) -> Sequence {
+        let scale = self.mood.scale();
+        let intensity = self.mood.intensity();
+        let lengths: &[NoteLength] = match intensity {
+            0 => &[NoteLength::Whole, NoteLength::Half],
+            1 => &[
+                NoteLength::Half,
+                NoteLength::Quarter,
+                NoteLength::QuarterDot,
+            ],
+            2 => &[
+                NoteLength::Quarter,
+                NoteLength::Eighth,
+                NoteLength::Eighth.dotted(),
+            ],
+            _ => &[NoteLength::Eighth, NoteLength::Eighth.triplet()],
+        };
+        let rest_chance = 0.3 - f32::from(intensity) * 0.1;
+
+        let mut notes: Vec<Note, SEQUENCE_LENGTH> = Vec::new();
+        let bar = NoteLength::Whole.value();
+        let mut time = 0;
+        while time < bar {
+            let left = bar - time;
+            let fitting: Vec<NoteLength, 5> = lengths
+                .iter()
+                .filter(|length| length.value() <= left)
+                .copied()
+                .collect();
+            let length = pick(&fitting, random)
+                .unwrap_or_else(|| NoteLength::Ticks(u8::try_from(left).unwrap_or(u8::MAX)));
+            let frequency = if time == 0 {
+                // Land on the chord on the downbeat so the melody follows the bass.
+                self.degree = nearest_degree(scale, chord) + to_u8(scale.len());
+                Frequency(Some(scale_pitch(scale, self.degree)))
+            } else if random.rand_float() < rest_chance {
+                Frequency(None)
+            } else {
+                let step = random.rand_range(0..5);
+                self.degree = match step {
+                    0 => self.degree.saturating_sub(2),
+                    1 => self.degree.saturating_sub(1),
+                    2 => self.degree.saturating_add(1),
+                    _ => self.degree.saturating_add(2),
+                }
+                .clamp(to_u8(scale.len()).div_euclid(2), to_u8(scale.len()) * 2);
+                Frequency(Some(scale_pitch(scale, self.degree)))
+            };
+            if notes.push(Note::new(frequency, length)).is_err() {
+                break;
+            }
+            time += length.value();
+        }
+        Sequence::new(notes)
+    }
+
+    fn bass(&self, chord: u8) -> Sequence {
+        let root = Frequency(Some(semitone(chord)));
+        let fifth = Frequency(Some(semitone(chord + 7)));
+        let octave = Frequency(Some(semitone(chord + 12)));
+        let mut notes: Vec<Note, SEQUENCE_LENGTH> = Vec::new();
+        let pattern: &[Note] = match self.mood.intensity() {
+            0 => &[Note::new(root, NoteLength::Whole)],
+            1 => &[
+                Note::new(root, NoteLength::Half),
+                Note::new(fifth, NoteLength::Half),
+            ],
+            _ => &[
+                Note::new(root, NoteLength::Quarter),
+                Note::new(root, NoteLength::Quarter),
+                Note::new(fifth, NoteLength::Quarter),
+                Note::new(octave, NoteLength::Quarter),
+            ],
+        };
+        notes
+            .extend_from_slice(pattern)
+            .expect("Bass pattern longer than SEQUENCE_LENGTH");
+        Sequence::new(notes)
+    }
+}
+
+fn pick<T: Copy>(options: &[T], random: &mut oorandom::Rand32) -> Option<T> {
+    if options.is_empty() {
+        return None;
+    }
+    let index = random.rand_range(0..to_u32(options.len()));
+    options.get(usize::try_from(index).ok()?).copied()
+}
+
+/// Lowest scale step at or above `semitones`, folded into one octave.
+fn nearest_degree(scale: &[u8], semitones: u8) -> u8 {
+    let position = scale
+        .iter()
+        .position(|&note| note >= semitones % 12)
+        .unwrap_or(0);
+    to_u8(position)
+}
+
+fn scale_pitch(scale: &[u8], degree: u8) -> u16 {
+    let len = to_u8(scale.len());
+    let octave = degree.div_euclid(len);
+    let step = scale
+        .get(usize::from(degree.rem_euclid(len)))
+        .copied()
+        .unwrap_or(0);
+    semitone(octave * 12 + step)
+}
+
+fn semitone(above_root: u8) -> u16 {
+    let above_root = i8::try_from(above_root).unwrap_or(i8::MAX);
+    ROOT.transpose(above_root).min(HIGHEST).frequency()
+}
+
+fn to_u8(value: usize) -> u8 {
+    u8::try_from(value).unwrap_or(u8::MAX)
+}
+
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
@@ -0,0 +1,314 @@
+// Numbers with a fixed binary point, so the simulation does the same thing bit for bit on any
+// machine. The game only runs on them with the `fixed-point` feature, see `scalar.rs`.
+//
+// Q16.16: sixteen bits either side of the point in an `i32`, the same size as the `f32` it
+// replaces, so the game state does not grow. That reaches about 32,767 pixels either way, and
+// squares of lengths past 181 pixels do not fit. Products and quotients round toward zero, as
+// `f32` truncated to 16 bits would, and results saturate instead of wrapping when they do not fit.
+//
+// The operators are never inlined: saturating takes a few instructions, and copied into every
+// formula they would push the cart past 64 KiB.
+
+use core::cmp::Ordering;
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+const FRACTION_BITS: u32 = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(i32::MAX);
+    pub const MIN: Self = Self(i32::MIN);
+    pub const PI: Self = Self(205_887);
+    pub const FRAC_PI_2: Self = Self(102_944);
+
+    pub const fn from_raw(raw: i32) -> Self {
+        Self(raw)
+    }
+
+    /// The nearest value to `value`; NaN is zero and anything too big is `MAX` or `MIN`.
+    #[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+    pub const fn from_f32(value: f32) -> Self {
+        let scaled = value * 65_536.0;
+        // `as` saturates and turns NaN into zero.
+        Self(if scaled < 0.0 {
+            (scaled - 0.5) as i32
+        } else {
+            (scaled + 0.5) as i32
+        })
+    }
+
+    #[allow(clippy::as_conversions, clippy::cast_precision_loss)]
+    pub const fn to_f32(self) -> f32 {
+        self.0 as f32 / 65_536.0
+    }
+
+    /// The raw value, for checksums.
+    #[allow(clippy::as_conversions, clippy::cast_sign_loss)]
+    pub const fn to_bits(self) -> u32 {
+        self.0 as u32
+    }
+
+    pub const fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    /// Zero for anything negative.
+    pub fn sqrt(self) -> Self {
+        let Ok(raw) = u64::try_from(self.0) else {
+            return Self::ZERO;
+        };
+        // √(raw / 2^16) * 2^16 = √(raw * 2^16), which is under 2^24.
+        Self::saturating((raw << FRACTION_BITS).isqrt())
+    }
+
+    /// √(x² + y²), squaring in 64 bits so it works for any length that fits itself.
+    pub fn hypot(x: Self, y: Self) -> Self {
+        let square = |value: Self| u64::from(value.0.unsigned_abs()).pow(2);
+        Self::saturating((square(x) + square(y)).isqrt())
+    }
+
+    /// A raw value worked out in 64 bits, or `MAX` or `MIN` if it does not fit back.
+    fn saturating<T: TryInto<i32> + PartialOrd + Default>(raw: T) -> Self {
+        let negative = raw < T::default();
+        raw.try_into()
+            .map_or(if negative { Self::MIN } else { Self::MAX }, Self)
+    }
+
+    /// `self` less the nearest multiple of π/2, and which multiple that was, modulo 4. The
+    /// multiple comes off with π/2 to 44 bits, so the error does not grow with the angle.
+    fn reduce_quarter_turns(self) -> (Self, i32) {
+        const WIDENING: u32 = 28;
+        const FRAC_PI_2_WIDE: i64 = 27_633_741_218_861;
+        let wide = i64::from(self.0) << WIDENING;
+        let quarter_turns = (wide + FRAC_PI_2_WIDE.div_euclid(2)).div_euclid(FRAC_PI_2_WIDE);
+        let rest = wide - quarter_turns * FRAC_PI_2_WIDE;
+        (
+            Self::saturating((rest + (1 << (WIDENING - 1))) >> WIDENING),
+            i32::try_from(quarter_turns.rem_euclid(4)).unwrap_or(0),
+        )
+    }
+
+    pub fn sin(self) -> Self {
+        self.sine_after(0)
+    }
+
+    /// cos x = sin(x + π/2).
+    pub fn cos(self) -> Self {
+        self.sine_after(1)
+    }
+
+    /// The sine of `self` plus `quarter_turns` times π/2.
+    fn sine_after(self, quarter_turns: i32) -> Self {
+        let (x, quadrant) = self.reduce_quarter_turns();
+        match (quadrant + quarter_turns).rem_euclid(4) {
+            0 => sin_kernel(x),
+            1 => cos_kernel(x),
+            2 => -sin_kernel(x),
+            _ => -cos_kernel(x),
+        }
+    }
+}
+
+// Taylor series between -π/4 and π/4, within the last bit by the x^7 term.
+fn sin_kernel(x: Fixed) -> Fixed {
+    let z = x * x;
+    x * (Fixed::from(1) - z / 6 * (Fixed::from(1) - z / 20 * (Fixed::from(1) - z / 42)))
+}
+
+fn cos_kernel(x: Fixed) -> Fixed {
+    let z = x * x;
+    Fixed::from(1) - z / 2 * (Fixed::from(1) - z / 12 * (Fixed::from(1) - z / 30))
+}
+
+impl From<i32> for Fixed {
+    fn from(value: i32) -> Self {
+        Self(value.saturating_mul(1 << FRACTION_BITS))
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+    #[inline(never)]
+    fn add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+    #[inline(never)]
+    fn sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+    // `/` rounds toward zero, where `>>` and `div_euclid` would round negatives away.
+    #[allow(clippy::integer_division)]
+    #[inline(never)]
+    fn mul(self, other: Self) -> Self {
+        Self::saturating(i64::from(self.0) * i64::from(other.0) / (1 << FRACTION_BITS))
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+    /// Dividing by zero gives `MAX` or `MIN`, or zero for zero.
+    #[allow(clippy::integer_division)]
+    #[inline(never)]
+    fn div(self, other: Self) -> Self {
+        if self.0 == 0 {
+            return Self::ZERO;
+        }
+        if other.0 == 0 {
+            return if self.0 < 0 { Self::MIN } else { Self::MAX };
+        }
+        Self::saturating((i64::from(self.0) << FRACTION_BITS) / i64::from(other.0))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+// Whole numbers and `f32` constants mix in directly, which keeps formulas readable. `f32`s are
+// rounded first, so the arithmetic itself stays fixed-point.
+
+impl Mul<i32> for Fixed {
+    type Output = Self;
+    fn mul(self, other: i32) -> Self {
+        Self(self.0.saturating_mul(other))
+    }
+}
+
+impl Div<i32> for Fixed {
+    type Output = Self;
+    fn div(self, other: i32) -> Self {
+        self / Self::from(other)
+    }
+}
+
+impl Add<i32> for Fixed {
+    type Output = Self;
+    fn add(self, other: i32) -> Self {
+        self + Self::from(other)
+    }
+}
+
+impl Sub<i32> for Fixed {
+    type Output = Self;
+    fn sub(self, other: i32) -> Self {
+        self - Self::from(other)
+    }
+}
+
+impl Add<f32> for Fixed {
+    type Output = Self;
+    fn add(self, other: f32) -> Self {
+        self + Self::from_f32(other)
+    }
+}
+
+impl Sub<f32> for Fixed {
+    type Output = Self;
+    fn sub(self, other: f32) -> Self {
+        self - Self::from_f32(other)
+    }
+}
+
+impl Mul<f32> for Fixed {
+    type Output = Self;
+    fn mul(self, other: f32) -> Self {
+        self * Self::from_f32(other)
+    }
+}
+
+impl Div<f32> for Fixed {
+    type Output = Self;
+    fn div(self, other: f32) -> Self {
+        self / Self::from_f32(other)
+    }
+}
+
+impl Add<Fixed> for f32 {
+    type Output = Fixed;
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed::from_f32(self) + other
+    }
+}
+
+impl Sub<Fixed> for f32 {
+    type Output = Fixed;
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed::from_f32(self) - other
+    }
+}
+
+impl Mul<Fixed> for f32 {
+    type Output = Fixed;
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed::from_f32(self) * other
+    }
+}
+
+impl Div<Fixed> for f32 {
+    type Output = Fixed;
+    fn div(self, other: Fixed) -> Fixed {
+        Fixed::from_f32(self) / other
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl AddAssign<f32> for Fixed {
+    fn add_assign(&mut self, other: f32) {
+        *self = *self + other;
+    }
+}
+
+impl PartialEq<f32> for Fixed {
+    fn eq(&self, other: &f32) -> bool {
+        *self == Self::from_f32(*other)
+    }
+}
+
+impl PartialOrd<f32> for Fixed {
+    fn partial_cmp(&self, other: &f32) -> Option<Ordering> {
+        Some(self.cmp(&Self::from_f32(*other)))
+    }
+}
+
+impl PartialEq<Fixed> for f32 {
+    fn eq(&self, other: &Fixed) -> bool {
+        Fixed::from_f32(*self) == *other
+    }
+}
+
+impl PartialOrd<Fixed> for f32 {
+    fn partial_cmp(&self, other: &Fixed) -> Option<Ordering> {
+        Some(Fixed::from_f32(*self).cmp(other))
+    }
+}
@@ -0,0 +1,165 @@
+use crate::arrangement::{Arrangement, Channel, Wave};
+use crate::composer::Composer;
+use crate::input::{self, Input, Playback, Recording};
+use crate::menu::Menu;
+use crate::palette::{self, Palette};
+use crate::save;
+use crate::scene::{Context, Scene, Scenes};
+use crate::settings::Settings;
+use crate::sfx::Sfx;
+use crate::song::{Song, SongPlayer};
+use crate::wasm4::{self, Framebuffer};
+use crate::world::World;
+
+// Nothing to seed from on WASM-4 yet, but replays keep it so that can change.
+const SEED: u64 = 0;
+const CURSOR_SIZE: u8 = 4;
+const MOUSE_CURSOR: [u8; 2] = [0b1110_1000, 0b1010_0001];
+static INTRO_SONG: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/intro.sds"));
+// Empty unless built with `SPACE_DELIVERY_REPLAY` set, see the README.
+static REPLAY: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/replay.sdr"));
+
+/// Everything the cart keeps between frames.
+pub struct Game {
+    frame: u32,
+    world: World,
+    music: Arrangement,
+    composer: Composer,
+    sfx: Sfx,
+    intro: Option<SongPlayer>,
+    scenes: Scenes,
+    settings: Settings,
+    colors: Palette,
+    recording: Recording,
+    playback: Option<Playback<'static>>,
+    // Held last frame, to catch the right click that exports the recording.
+    mouse_buttons: u8,
+    // Taken in `start`, so nothing draws before then.
+    screen: Option<Framebuffer>,
+}
+
+impl Game {
+    pub const fn new() -> Self {
+        Self {
+            frame: 0,
+            world: World::new(),
+            music: Arrangement::new(
+                Some(Channel::new(Wave::Triangle)),
+                Some(Channel::new(Wave::Pulse1)),
+                Some(Channel::new(Wave::Pulse2)),
+                Some(Channel::new(Wave::Noise)),
+            ),
+            composer: Composer::new(),
+            sfx: Sfx::new(),
+            intro: None,
+            scenes: Scenes::new(Scene::Title(Menu::new())),
+            settings: Settings::new(),
+            colors: Palette::new(),
+            recording: Recording::new(),
+            playback: None,
+            mouse_buttons: 0,
+            screen: None,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.screen = Framebuffer::take();
+        wasm4::DrawColors::new()
+            .first(1)
+            .second(2)
+            .third(3)
+            .fourth(4)
+            .set();
+        match Song::parse(INTRO_SONG) {
+            Ok(song) => self.intro = Some(SongPlayer::new(song, false)),
+            Err(error) => wasm4::trace(error.message()),
+        }
+        if !REPLAY.is_empty() {
+            match Playback::parse(REPLAY) {
+                Ok(replay) => self.playback = Some(replay),
+                Err(error) => wasm4::trace(error.message()),
+            }
+        }
+        let save = save::load();
+        self.world.set_routes(save.routes);
+        self.settings = save.settings;
+        self.world.set_upgrades(save.upgrades);
+        if let Some(replay) = self.playback.as_ref() {
+            // Straight into the recorded run, with the ship as it was then.
+            self.world.set_seed(replay.seed());
+            self.world.set_replay_upgrades(*replay.upgrades());
+            self.world.start(replay.mode(), replay.lineup());
+            self.scenes = Scenes::new(Scene::Playing);
+        } else {
+            self.world.set_seed(SEED);
+        }
+    }
+
+    pub fn update(&mut self, live: Input) {
+        let mut random = oorandom::Rand32::new(self.frame.into());
+        if let Some(song) = self.intro.as_mut() {
+            if !song.update(&mut self.music) {
+                self.intro = None;
+            }
+        } else {
+            self.composer.set_mood(self.world.mood());
+            self.composer.compose(&mut self.music, &mut random);
+        }
+
+        self.scenes.update(
+            live,
+            &mut Context {
+                world: &mut self.world,
+                sfx: &mut self.sfx,
+                settings: &mut self.settings,
+                recording: &mut self.recording,
+                playback: &mut self.playback,
+            },
+        );
+        self.export(live);
+
+        self.sfx.set_volume(self.settings.volume_percent());
+        self.sfx.update(&mut self.music);
+        if !self.settings.music {
+            self.music.set_gain(0);
+        }
+        self.music.update();
+        self.frame += 1;
+    }
+
+    /// Right click prints the session so far, for attaching to bug reports.
+    fn export(&mut self, live: Input) {
+        let clicked = live.mouse_buttons & !self.mouse_buttons;
+        self.mouse_buttons = live.mouse_buttons;
+        if clicked & wasm4::MOUSE_RIGHT == 0 || self.playback.is_some() {
+            return;
+        }
+        if self.recording.is_full() {
+            wasm4::trace("replay: recording is full, this is only the start of the session");
+        }
+        input::trace_hex("replay: ", self.recording.as_bytes());
+        input::trace_end(self.recording.frames(), self.world.checksum());
+    }
+
+    pub fn draw(&mut self, live: Input) {
+        let sector = self.scenes.shows_world().then(|| self.world.sector());
+        self.colors.update(
+            palette::theme(self.settings.palette),
+            self.scenes.brightness(),
+            sector,
+        );
+        if let Some(screen) = self.screen.as_mut() {
+            self.scenes.draw(&self.world, &self.settings, screen);
+        }
+
+        wasm4::DrawColors::new().first(3).set();
+        wasm4::blit(
+            &MOUSE_CURSOR,
+            live.mouse.0.into(),
+            live.mouse.1.into(),
+            CURSOR_SIZE.into(),
+            CURSOR_SIZE.into(),
+            wasm4::BLIT_1BPP,
+        );
+    }
+}
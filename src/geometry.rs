@@ -0,0 +1,396 @@
+// Points, shapes and the tests between them, in `Scalar`s. World coordinates have y growing
+// down the screen, so a positive angle or cross product turns clockwise as seen by the player.
+
+use crate::scalar::{cos, hypot, scalar, sin, Scalar, EPSILON, INFINITY};
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Width and height of the WASM-4 screen in pixels.
+pub const SCREEN_SIZE: f32 = 160.0;
+
+#[derive(Clone, Copy)]
+pub struct Vec2 {
+    pub x: Scalar,
+    pub y: Scalar,
+}
+
+impl Vec2 {
+    pub const ZERO: Self = Self::from_f32(0.0, 0.0);
+
+    pub const fn new(x: Scalar, y: Scalar) -> Self {
+        Self { x, y }
+    }
+
+    /// A vector from `f32`s, such as constants, whatever `Scalar` is.
+    pub const fn from_f32(x: f32, y: f32) -> Self {
+        Self::new(scalar(x), scalar(y))
+    }
+
+    /// A vector of length 1 at `rad` from the x axis.
+    pub fn from_angle(rad: Scalar) -> Self {
+        Self::new(cos(rad), sin(rad))
+    }
+
+    pub fn distance(self, other: Self) -> Scalar {
+        let delta_x = self.x - other.x;
+        let delta_y = self.y - other.y;
+
+        hypot(delta_x, delta_y)
+    }
+
+    pub fn magnitude(self) -> Scalar {
+        hypot(self.x, self.y)
+    }
+
+    pub fn magnitude_squared(self) -> Scalar {
+        self.dot(self)
+    }
+
+    pub fn normalized(self) -> Self {
+        let magnitude = self.magnitude();
+        if magnitude.abs() < EPSILON || (magnitude - 1.0).abs() < EPSILON {
+            self
+        } else {
+            self / magnitude
+        }
+    }
+
+    pub fn rotated(self, rad: Scalar) -> Self {
+        Self {
+            x: self.x * cos(rad) - self.y * sin(rad),
+            y: self.x * sin(rad) + self.y * cos(rad),
+        }
+    }
+
+    /// The same vector turned a quarter clockwise, exactly.
+    pub fn perpendicular(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    pub fn dot(self, other: Self) -> Scalar {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The point `amount` of the way to `other`; 0 is `self` and 1 is `other`.
+    pub fn lerp(self, other: Self, amount: Scalar) -> Self {
+        self + (other - self) * amount
+    }
+
+    /// Each component on its own limited to between those of `min` and `max`.
+    // `Fixed` compares through traits, which a `const fn` cannot call.
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn clamped(self, min: Self, max: Self) -> Self {
+        Self::new(self.x.max(min.x).min(max.x), self.y.max(min.y).min(max.y))
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Self) {
+        *self = Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        };
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl Mul<Scalar> for Vec2 {
+    type Output = Self;
+    fn mul(self, other: Scalar) -> Self {
+        Self {
+            x: self.x * other,
+            y: self.y * other,
+        }
+    }
+}
+
+impl MulAssign<Scalar> for Vec2 {
+    fn mul_assign(&mut self, other: Scalar) {
+        *self = *self * other;
+    }
+}
+
+impl Div<Scalar> for Vec2 {
+    type Output = Self;
+    fn div(self, other: Scalar) -> Self {
+        if other.abs() > EPSILON {
+            Self {
+                x: self.x / other,
+                y: self.y / other,
+            }
+        } else {
+            self
+        }
+    }
+}
+
+// Constants stay `f32` in formulas with fixed point too.
+#[cfg(feature = "fixed-point")]
+impl Mul<f32> for Vec2 {
+    type Output = Self;
+    fn mul(self, other: f32) -> Self {
+        self * scalar(other)
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl MulAssign<f32> for Vec2 {
+    fn mul_assign(&mut self, other: f32) {
+        *self = *self * other;
+    }
+}
+
+#[cfg(feature = "fixed-point")]
+impl Div<f32> for Vec2 {
+    type Output = Self;
+    fn div(self, other: f32) -> Self {
+        self / scalar(other)
+    }
+}
+
+/// An upright rectangle. The top and left edges are inside, the bottom and right ones are not.
+#[derive(Clone, Copy)]
+pub struct Rect {
+    pub top_left: Vec2,
+    pub size: Vec2,
+}
+
+impl Rect {
+    pub const fn new(top_left: Vec2, size: Vec2) -> Self {
+        Self { top_left, size }
+    }
+
+    /// What fits on the screen with `top_left` in its corner.
+    pub const fn screen(top_left: Vec2) -> Self {
+        Self::new(top_left, Vec2::from_f32(SCREEN_SIZE, SCREEN_SIZE))
+    }
+
+    pub const fn change_pos(&mut self, top_left: Vec2) {
+        self.top_left = top_left;
+    }
+
+    /// The same rectangle moved by `by`.
+    pub fn offset(&self, by: Vec2) -> Self {
+        Self::new(self.top_left + by, self.size)
+    }
+
+    /// The same centre with `by` more on every side, or less when negative.
+    pub fn grown(&self, by: f32) -> Self {
+        Self::new(
+            self.top_left - Vec2::from_f32(by, by),
+            self.size + Vec2::from_f32(by * 2.0, by * 2.0),
+        )
+    }
+
+    pub fn bottom_right(&self) -> Vec2 {
+        self.top_left + self.size
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.center_mul(1.0)
+    }
+
+    pub fn center_mul(&self, mul: f32) -> Vec2 {
+        Vec2::new(
+            self.top_left.x + (self.size.x * mul / 2.),
+            self.top_left.y + (self.size.y * mul / 2.),
+        )
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let bottom_right = self.bottom_right();
+        point.x >= self.top_left.x
+            && point.y >= self.top_left.y
+            && point.x < bottom_right.x
+            && point.y < bottom_right.y
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The part both cover, if any.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let top_left = Vec2::new(
+            self.top_left.x.max(other.top_left.x),
+            self.top_left.y.max(other.top_left.y),
+        );
+        let (ours, theirs) = (self.bottom_right(), other.bottom_right());
+        let bottom_right = Vec2::new(ours.x.min(theirs.x), ours.y.min(theirs.y));
+        (bottom_right.x > top_left.x && bottom_right.y > top_left.y)
+            .then(|| Self::new(top_left, bottom_right - top_left))
+    }
+
+    /// The smallest rectangle around both.
+    pub fn union(&self, other: &Self) -> Self {
+        let top_left = Vec2::new(
+            self.top_left.x.min(other.top_left.x),
+            self.top_left.y.min(other.top_left.y),
+        );
+        let (ours, theirs) = (self.bottom_right(), other.bottom_right());
+        let bottom_right = Vec2::new(ours.x.max(theirs.x), ours.y.max(theirs.y));
+        Self::new(top_left, bottom_right - top_left)
+    }
+
+    /// The point inside or on the edge nearest to `point`.
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        point.clamped(self.top_left, self.bottom_right())
+    }
+
+    pub fn intersects_circle(&self, circle: &Circle) -> bool {
+        self.closest_point(circle.center).distance(circle.center) <= circle.radius
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: Scalar,
+}
+
+impl Circle {
+    pub const fn new(center: Vec2, radius: Scalar) -> Self {
+        Self { center, radius }
+    }
+
+    /// Whether `point` is strictly inside, the edge is not.
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance(point) < self.radius
+    }
+
+    /// The smallest upright rectangle around it.
+    pub fn bounds(&self) -> Rect {
+        Rect::new(
+            self.center - Vec2::new(self.radius, self.radius),
+            Vec2::new(self.radius * 2.0, self.radius * 2.0),
+        )
+    }
+}
+
+/// The straight line between two points.
+#[derive(Clone, Copy)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Segment {
+    pub const fn new(start: Vec2, end: Vec2) -> Self {
+        Self { start, end }
+    }
+
+    /// The point on the segment nearest to `point`.
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let along = self.end - self.start;
+        let length_squared = along.magnitude_squared();
+        if length_squared < EPSILON {
+            return self.start;
+        }
+        let amount =
+            ((point - self.start).dot(along) / length_squared).clamp(scalar(0.0), scalar(1.0));
+        self.start.lerp(self.end, amount)
+    }
+
+    /// Whether any of it is within `circle`, edge included, such as a fast ship passing
+    /// through a planet between two frames.
+    pub fn intersects_circle(&self, circle: &Circle) -> bool {
+        self.closest_point(circle.center).distance(circle.center) <= circle.radius
+    }
+}
+
+/// A half line from `origin`; `direction` always has length 1, so distances along it are in
+/// world units.
+#[derive(Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec2,
+    direction: Vec2,
+}
+
+impl Ray {
+    /// `None` when `direction` is too short to point anywhere.
+    pub fn new(origin: Vec2, direction: Vec2) -> Option<Self> {
+        let length = direction.magnitude();
+        (length >= EPSILON).then(|| Self {
+            origin,
+            direction: direction / length,
+        })
+    }
+
+    pub const fn direction(&self) -> Vec2 {
+        self.direction
+    }
+
+    /// The point `distance` along.
+    pub fn at(&self, distance: Scalar) -> Vec2 {
+        self.origin + self.direction * distance
+    }
+
+    /// How far along the ray goes into and comes out of `rect`. Going in is at 0 when the
+    /// ray starts inside.
+    pub fn cast_rect(&self, rect: &Rect) -> Option<(Scalar, Scalar)> {
+        let bottom_right = rect.bottom_right();
+        let mut into = scalar(0.0);
+        let mut out = INFINITY;
+        // Each pair of edges lets the ray through over one span; the rectangle is where
+        // the spans overlap.
+        for (origin, direction, low, high) in [
+            (
+                self.origin.x,
+                self.direction.x,
+                rect.top_left.x,
+                bottom_right.x,
+            ),
+            (
+                self.origin.y,
+                self.direction.y,
+                rect.top_left.y,
+                bottom_right.y,
+            ),
+        ] {
+            if direction.abs() < EPSILON {
+                if origin < low || origin > high {
+                    return None;
+                }
+                continue;
+            }
+            let (near, far) = ((low - origin) / direction, (high - origin) / direction);
+            into = into.max(near.min(far));
+            out = out.min(near.max(far));
+        }
+        (into <= out).then_some((into, out))
+    }
+}
@@ -1,12 +1,15 @@
 #![allow(clippy::integer_division, clippy::as_conversions)]
-use crate::wasm4;
+use crate::geometry::{Circle, Rect, Vec2};
+use crate::math::floor;
+use crate::scalar::{to_f32, Scalar};
+use crate::wasm4::{self, Framebuffer};
 
 const COLORS: u8 = 4;
 const MAX_BRIGHTNESS: u8 = 255;
 const DISPERSION_MATRIX_SIZE: u8 = 9;
 const DISPERSED: [u8; DISPERSION_MATRIX_SIZE as usize] = [1, 7, 4, 5, 8, 3, 6, 2, 9];
 
-pub fn draw_pixel(x: u8, y: u8, brightness: u8) {
+pub fn draw_pixel(screen: &mut Framebuffer, x: u8, y: u8, brightness: u8) {
     if x > 160 || y > 160 {
         return;
     }
@@ -16,37 +19,66 @@ pub fn draw_pixel(x: u8, y: u8, brightness: u8) {
     let color_base = brightness / step;
     // Get the value from the matrix.
     let idx = ((x - y * 3) % DISPERSION_MATRIX_SIZE) as usize;
-    let threshhold = unsafe { DISPERSED.get_unchecked(idx) * (step / DISPERSION_MATRIX_SIZE) };
+    let threshhold = DISPERSED.get(idx).copied().unwrap_or(0) * (step / DISPERSION_MATRIX_SIZE);
     let diff = brightness % step;
-    let add_color = if diff >= threshhold { 1 } else { 0 };
+    let add_color = u8::from(diff >= threshhold);
     let draw_color = color_base + add_color;
-    let draw_color = 1 + u16::from(draw_color);
+    let draw_color = 1 + draw_color;
 
-    unsafe {
-        *wasm4::DRAW_COLORS = draw_color;
-    }
-    screen_pixel(x, y);
+    let colors = wasm4::DrawColors::new().first(draw_color);
+    colors.set();
+    screen_pixel(screen, x, y, colors);
 }
 
-fn screen_pixel(x: u8, y: u8) {
-    // The byte index into the framebuffer that contains (x, y)
-    let idx = (y as usize * 160 + x as usize) >> 2;
-
-    // Calculate the bits within the byte that corresponds to our position
-    let shift = (x as u8 & 0b11) << 1;
-    let mask = 0b11 << shift;
+fn screen_pixel(screen: &mut Framebuffer, x: u8, y: u8, colors: wasm4::DrawColors) {
+    let palette_color = colors.first_color();
+    if palette_color == 0 {
+        // Transparent
+        return;
+    }
+    let color = (palette_color - 1) & 0b11;
+    screen.set_pixel(x.into(), y.into(), color);
+}
 
-    #[allow(clippy::indexing_slicing)]
-    unsafe {
-        let palette_color: u8 = (*wasm4::DRAW_COLORS & 0xf) as u8;
-        if palette_color == 0 {
-            // Transparent
-            return;
+/// Fills the part of `disc` that `view` shows, each pixel as bright as `shade` makes
+/// its distance from the centre. Only the pixels around the disc are looked at.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn draw_disc(
+    screen: &mut Framebuffer,
+    view: &Rect,
+    disc: &Circle,
+    shade: &dyn Fn(Scalar) -> u8,
+) {
+    let Some(area) = view.intersection(&disc.bounds()) else {
+        return;
+    };
+    let first = area.top_left - view.top_left;
+    let last = area.bottom_right() - view.top_left;
+    // Whole pixels from the first one the disc touches; casting clamps into the screen.
+    let (first_x, first_y) = (floor(to_f32(first.x)) as u8, floor(to_f32(first.y)) as u8);
+    let (last_x, last_y) = (
+        (to_f32(last.x) as u8).min(159),
+        (to_f32(last.y) as u8).min(159),
+    );
+    for screen_y in first_y..=last_y {
+        for screen_x in first_x..=last_x {
+            let point = view.top_left + Vec2::from_f32(f32::from(screen_x), f32::from(screen_y));
+            let distance = disc.center.distance(point);
+            if distance > disc.radius {
+                continue;
+            }
+            draw_pixel(screen, screen_x, screen_y, shade(distance));
         }
-        let color = (palette_color - 1) & 0b11;
-
-        let framebuffer = wasm4::FRAMEBUFFER.as_mut().expect("fb ref");
-
-        framebuffer[idx] = (color << shift) | (framebuffer[idx] & !mask);
     }
 }
+
+/// A line between two points on the screen, in the current first draw colour.
+#[allow(clippy::cast_possible_truncation)]
+pub fn line(from: Vec2, to: Vec2) {
+    wasm4::line(
+        to_f32(from.x) as i32,
+        to_f32(from.y) as i32,
+        to_f32(to.x) as i32,
+        to_f32(to.y) as i32,
+    );
+}
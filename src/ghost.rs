@@ -0,0 +1,208 @@
+use crate::geometry::{Rect, Vec2};
+use crate::gfx::draw_pixel;
+use crate::scalar::to_f32;
+use crate::wasm4::Framebuffer;
+use heapless::Vec;
+
+/// How often a route remembers where the ship was.
+pub const SAMPLE_FRAMES: u16 = 8;
+/// Longest route worth keeping, about 13 seconds; slower deliveries are not a best run anyway.
+pub const MAX_SAMPLES: usize = 100;
+pub const MAX_ROUTES: usize = 4;
+const TRAIL: u8 = 5;
+const BRIGHTNESS: u8 = 150;
+
+/// Position rounded to whole pixels, which is all a ghost needs.
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+const fn point(pos: Vec2) -> (i16, i16) {
+    (to_f32(pos.x) as i16, to_f32(pos.y) as i16)
+}
+
+/// The way a ship flew to a delivery target, from the moment it was picked.
+/// Stored as steps between samples so a whole route fits in a couple of hundred bytes.
+#[derive(Clone)]
+pub struct Route {
+    pub target: (i16, i16),
+    pub start: (i16, i16),
+    pub frames: u16,
+    pub steps: Vec<(i8, i8), MAX_SAMPLES>,
+}
+
+impl Route {
+    fn points(&self) -> impl Iterator<Item = (i16, i16)> + '_ {
+        core::iter::once(self.start).chain(self.steps.iter().scan(self.start, |at, &(x, y)| {
+            *at = (at.0.saturating_add(x.into()), at.1.saturating_add(y.into()));
+            Some(*at)
+        }))
+    }
+}
+
+/// Follows the ship during a delivery.
+pub struct RouteRecorder {
+    route: Route,
+    // Where the last step ended, so rounding never adds up.
+    at: (i16, i16),
+    too_slow: bool,
+}
+
+impl RouteRecorder {
+    pub const fn new(target: Vec2, start: Vec2) -> Self {
+        let start = point(start);
+        Self {
+            route: Route {
+                target: point(target),
+                start,
+                frames: 0,
+                steps: Vec::new(),
+            },
+            at: start,
+            too_slow: false,
+        }
+    }
+
+    pub fn update(&mut self, pos: Vec2) {
+        self.route.frames = self.route.frames.saturating_add(1);
+        if self.route.frames.is_multiple_of(SAMPLE_FRAMES) {
+            self.sample(pos);
+        }
+    }
+
+    fn sample(&mut self, pos: Vec2) {
+        let (x, y) = point(pos);
+        let step = |from: i16, to: i16| {
+            i8::try_from(
+                to.saturating_sub(from)
+                    .clamp(i8::MIN.into(), i8::MAX.into()),
+            )
+            .unwrap_or_default()
+        };
+        let step = (step(self.at.0, x), step(self.at.1, y));
+        if self.route.steps.push(step).is_err() {
+            self.too_slow = true;
+            return;
+        }
+        self.at = (
+            self.at.0.saturating_add(step.0.into()),
+            self.at.1.saturating_add(step.1.into()),
+        );
+    }
+
+    /// The finished route, unless it ran out of samples.
+    pub fn finish(mut self, pos: Vec2) -> Option<Route> {
+        if !self.route.frames.is_multiple_of(SAMPLE_FRAMES) {
+            self.sample(pos);
+        }
+        (!self.too_slow).then_some(self.route)
+    }
+}
+
+/// The fastest route known to each of a few targets.
+pub struct Routes {
+    routes: Vec<Route, MAX_ROUTES>,
+}
+
+impl Routes {
+    pub const fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    pub fn find(&self, target: Vec2) -> Option<&Route> {
+        let target = point(target);
+        self.routes.iter().find(|route| route.target == target)
+    }
+
+    /// Keeps `route` if it beats the one to the same target, pushing out the slowest
+    /// route when there is no room. Returns whether anything changed.
+    pub fn insert(&mut self, route: Route) -> bool {
+        if let Some(best) = self
+            .routes
+            .iter_mut()
+            .find(|best| best.target == route.target)
+        {
+            if route.frames >= best.frames {
+                return false;
+            }
+            *best = route;
+            return true;
+        }
+        if let Err(route) = self.routes.push(route) {
+            if let Some(slowest) = self.routes.iter_mut().max_by_key(|route| route.frames) {
+                *slowest = route;
+            }
+        }
+        true
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Route> {
+        self.routes.iter()
+    }
+}
+
+/// Plays a best route back next to the ship.
+pub struct Ghost {
+    points: Vec<(i16, i16), { MAX_SAMPLES + 1 }>,
+    frame: u16,
+}
+
+impl Ghost {
+    pub fn new(route: &Route) -> Self {
+        Self {
+            points: route.points().collect(),
+            frame: 0,
+        }
+    }
+
+    pub const fn update(&mut self) {
+        self.frame = self.frame.saturating_add(1);
+    }
+
+    /// Moves the whole route by `by`, as the world moves back.
+    pub fn shift(&mut self, by: Vec2) {
+        let (x, y) = point(by);
+        for point in &mut self.points {
+            *point = (point.0.saturating_add(x), point.1.saturating_add(y));
+        }
+    }
+
+    /// Where the ghost was `frame` frames after the pickup, between two samples.
+    fn position(&self, frame: u16) -> Option<Vec2> {
+        let index = usize::from(frame.div_euclid(SAMPLE_FRAMES));
+        let &(x0, y0) = self.points.get(index)?;
+        let &(x1, y1) = self.points.get(index + 1).unwrap_or(&(x0, y0));
+        let t = f32::from(frame % SAMPLE_FRAMES) / f32::from(SAMPLE_FRAMES);
+        let from = Vec2::from_f32(x0.into(), y0.into());
+        let to = Vec2::from_f32(x1.into(), y1.into());
+        Some(from + (to - from) * t)
+    }
+
+    /// A dithered dot with a fading tail, which reads as see-through on four colours.
+    #[allow(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn draw(&self, view: &Rect, screen: &mut Framebuffer) {
+        for age in 0..TRAIL {
+            let Some(frame) = self.frame.checked_sub(u16::from(age) * 3) else {
+                break;
+            };
+            let Some(pos) = self.position(frame) else {
+                continue;
+            };
+            // Where the ship's lines meet, as in `Player::draw2`.
+            let at = pos - view.top_left - Vec2::from_f32(0.0, 3.0);
+            if !(1.0..159.0).contains(&at.x) || !(1.0..159.0).contains(&at.y) {
+                continue;
+            }
+            let brightness = BRIGHTNESS - age * 25;
+            let (x, y) = (to_f32(at.x) as u8, to_f32(at.y) as u8);
+            draw_pixel(screen, x, y, brightness);
+            if age == 0 {
+                draw_pixel(screen, x + 1, y, brightness);
+                draw_pixel(screen, x, y + 1, brightness);
+                draw_pixel(screen, x - 1, y, brightness);
+                draw_pixel(screen, x, y - 1, brightness);
+            }
+        }
+    }
+}
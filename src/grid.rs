@@ -0,0 +1,208 @@
+// Which of a buffer of up to 255 things are near a point, without looking at all of them. Each
+// thing goes in the bucket for the square cell it is in. Space has no edges, so the cells repeat
+// every `CELLS` across and down; things that far apart share a bucket, which only costs the
+// caller a distance check it makes anyway. Things are numbered the way a `Ring` of `CAPACITY`
+// numbers what is written to it, so a grid kept in step with one indexes its slice.
+
+use crate::geometry::{Circle, Rect, Vec2};
+use crate::math::floor;
+use crate::scalar::{to_f32, Scalar, INFINITY};
+
+/// The most things a grid keeps track of; more than that are left out.
+pub const CAPACITY: usize = 255;
+// Pixels across a cell.
+const CELL_SIZE: f32 = 128.0;
+// Cells across and down before they repeat.
+const CELLS: i32 = 16;
+const BUCKETS: usize = 256;
+// Ends a bucket. Indices only go up to 254, so it is never one.
+const END: u8 = u8::MAX;
+
+pub struct Grid {
+    // The first index in each bucket, and after each index the next one in its bucket.
+    heads: [u8; BUCKETS],
+    next: [u8; CAPACITY],
+    // The bucket each index is in, to take it out again when its number comes round.
+    buckets: [u8; CAPACITY],
+    // The number the next thing gets, and whether they have all been handed out once.
+    write_at: u8,
+    filled: bool,
+}
+
+impl Grid {
+    pub const fn new() -> Self {
+        Self {
+            heads: [END; BUCKETS],
+            next: [END; CAPACITY],
+            buckets: [0; CAPACITY],
+            write_at: 0,
+            filled: false,
+        }
+    }
+
+    pub const fn clear(&mut self) {
+        self.heads = [END; BUCKETS];
+        self.write_at = 0;
+        self.filled = false;
+    }
+
+    /// Adds a thing at `pos`, in place of the oldest one once the grid is full, the same way
+    /// `Ring::write` does.
+    pub fn insert(&mut self, pos: Vec2) {
+        let index = self.write_at;
+        if self.filled {
+            self.remove(index);
+        }
+        self.link(index, pos);
+        self.write_at = index + 1;
+        if usize::from(self.write_at) == CAPACITY {
+            self.write_at = 0;
+            self.filled = true;
+        }
+    }
+
+    /// Puts every one of `items`, the buffer the grid is kept in step with, back in the bucket
+    /// for where `position` says it is now, after they have all moved.
+    pub fn relink<T>(&mut self, items: &[T], position: impl Fn(&T) -> Vec2) {
+        self.heads = [END; BUCKETS];
+        for (index, item) in items.iter().enumerate() {
+            if let Ok(index) = u8::try_from(index) {
+                self.link(index, position(item));
+            }
+        }
+    }
+
+    /// Puts `index` at the head of the bucket for `pos`.
+    fn link(&mut self, index: u8, pos: Vec2) {
+        let bucket = bucket(cell(pos.x), cell(pos.y));
+        let (Some(head), Some(next), Some(slot)) = (
+            self.heads.get_mut(bucket),
+            self.next.get_mut(usize::from(index)),
+            self.buckets.get_mut(usize::from(index)),
+        ) else {
+            return;
+        };
+        *next = *head;
+        *head = index;
+        *slot = u8::try_from(bucket).unwrap_or(0);
+    }
+
+    /// Unlinks `index` from its bucket.
+    fn remove(&mut self, index: u8) {
+        let bucket = self.buckets.get(usize::from(index)).copied().unwrap_or(0);
+        let after = self.next.get(usize::from(index)).copied().unwrap_or(END);
+        let Some(head) = self.heads.get_mut(usize::from(bucket)) else {
+            return;
+        };
+        let mut current = *head;
+        if current == index {
+            *head = after;
+            return;
+        }
+        while let Some(next) = self.next.get_mut(usize::from(current)) {
+            if *next == index {
+                *next = after;
+                return;
+            }
+            current = *next;
+        }
+    }
+
+    /// Everything in the cells that `area` touches, which is all of what is inside it and
+    /// maybe some more nearby or far away.
+    pub fn near(&self, area: &Rect) -> Indices {
+        let bottom_right = area.bottom_right();
+        let (first_x, last_x) = span(area.top_left.x, bottom_right.x);
+        let (first_y, last_y) = span(area.top_left.y, bottom_right.y);
+        let mut found = Indices([0; 8]);
+        for y in first_y..=last_y {
+            for x in first_x..=last_x {
+                let mut index = self.heads.get(bucket(x, y)).copied().unwrap_or(END);
+                while index != END {
+                    found.insert(index);
+                    index = self.next.get(usize::from(index)).copied().unwrap_or(END);
+                }
+            }
+        }
+        found
+    }
+
+    /// Those of `items`, the buffer the grid was filled from, that may be in `area`, in the
+    /// order they are in there.
+    pub fn within<'a, T>(&self, items: &'a [T], area: &Rect) -> impl Iterator<Item = &'a T> {
+        self.near(area).select(items)
+    }
+
+    /// The closest of `items` to `pos`, up to `range` away. `position` says where one is, or
+    /// `None` to pass it over.
+    pub fn nearest<'a, T>(
+        &self,
+        items: &'a [T],
+        pos: Vec2,
+        range: Scalar,
+        position: impl Fn(&T) -> Option<Vec2>,
+    ) -> Option<&'a T> {
+        let mut nearest = None;
+        let mut best = INFINITY;
+        for item in self.within(items, &Circle::new(pos, range).bounds()) {
+            let Some(distance) = position(item).map(|at| at.distance(pos)) else {
+                continue;
+            };
+            if distance <= range && distance < best {
+                best = distance;
+                nearest = Some(item);
+            }
+        }
+        nearest
+    }
+}
+
+/// A set of indices into a grid's buffer, which iterates from the lowest.
+pub struct Indices([u32; 8]);
+
+impl Indices {
+    /// The ones of `items` in the set, in order.
+    pub fn select<T>(self, items: &[T]) -> impl Iterator<Item = &T> {
+        self.filter_map(|index| items.get(index))
+    }
+
+    fn insert(&mut self, index: u8) {
+        if let Some(word) = self.0.get_mut(usize::from(index.div_euclid(32))) {
+            *word |= 1 << index.rem_euclid(32);
+        }
+    }
+}
+
+impl Iterator for Indices {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let (word_index, word) = self
+            .0
+            .iter_mut()
+            .enumerate()
+            .find(|&(_, &mut word)| word != 0)?;
+        let bit = word.trailing_zeros();
+        // Clears the lowest set bit.
+        *word &= *word - 1;
+        usize::try_from(bit).ok().map(|bit| word_index * 32 + bit)
+    }
+}
+
+/// The cell `x` is in, along one axis.
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+fn cell(x: Scalar) -> i32 {
+    floor(to_f32(x) / CELL_SIZE) as i32
+}
+
+/// The first and last cells from `low` to `high` along one axis, with a pixel to spare either
+/// side so rounding never loses one at an edge, and no more than `CELLS` of them.
+fn span(low: Scalar, high: Scalar) -> (i32, i32) {
+    let first = cell(low - 1.0);
+    let last = cell(high + 1.0).min(first.saturating_add(CELLS - 1));
+    (first, last)
+}
+
+fn bucket(x: i32, y: i32) -> usize {
+    usize::try_from(y.rem_euclid(CELLS) * CELLS + x.rem_euclid(CELLS)).unwrap_or(0)
+}
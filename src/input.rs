@@ -0,0 +1,304 @@
+use crate::mode::{Lineup, Mode};
+use crate::player::MAX_PLAYERS;
+use crate::upgrade::Upgrades;
+use crate::wasm4::{self, Gamepad, Mouse};
+use heapless::Vec;
+
+// REPLAY FORMAT
+// "SDRP" | version | world seed (u64 little endian) | mode (see `Mode::to_byte`)
+//     | players | flags (see `Lineup::to_bytes`) | upgrades (see `Upgrades::to_bytes`)
+// runs of identical frames: frame count (1..=255), one gamepad per player, mouse buttons,
+//     then mouse x and y (i16 little endian) only when a button is held
+// Version 3 has no upgrades and plays without any, and version 2 has no lineup either and is
+// always one player.
+const MAGIC: &[u8] = b"SDRP";
+const VERSION: u8 = 4;
+const HEADER_SIZE: usize = 24;
+const LINEUP_VERSION: u8 = 3;
+const LINEUP_HEADER_SIZE: usize = 16;
+const SOLO_VERSION: u8 = 2;
+const SOLO_HEADER_SIZE: usize = 14;
+pub const RECORDING_SIZE: usize = 2048;
+
+/// Everything the players can do in one frame.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Input {
+    pub gamepads: [u8; MAX_PLAYERS],
+    pub mouse: (i16, i16),
+    pub mouse_buttons: u8,
+}
+
+impl Input {
+    pub const fn read() -> Self {
+        let mouse = Mouse::read();
+        Self {
+            gamepads: [
+                Gamepad::read(0),
+                Gamepad::read(1),
+                Gamepad::read(2),
+                Gamepad::read(3),
+            ],
+            mouse: (mouse.x, mouse.y),
+            mouse_buttons: mouse.buttons,
+        }
+    }
+
+    /// Player one's buttons, which also work the menus.
+    pub const fn gamepad(&self) -> u8 {
+        self.gamepads[0]
+    }
+
+    /// What a replay keeps: the game only looks at the mouse while a button is held,
+    /// and at the gamepads of ships in the run, so the rest is dropped to keep runs long.
+    fn recorded(mut self, players: usize) -> Self {
+        if self.mouse_buttons == 0 {
+            self.mouse = (0, 0);
+        }
+        for gamepad in self.gamepads.iter_mut().skip(players) {
+            *gamepad = 0;
+        }
+        self
+    }
+}
+
+/// Run-length encodes input from the first frame of a run; stops quietly once the buffer is full.
+pub struct Recording {
+    data: Vec<u8, RECORDING_SIZE>,
+    last: Option<Input>,
+    // Where the count of the current run is.
+    run: usize,
+    frames: u32,
+    full: bool,
+    players: usize,
+}
+
+impl Recording {
+    pub const fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            last: None,
+            run: 0,
+            frames: 0,
+            full: false,
+            players: 1,
+        }
+    }
+
+    pub fn start(&mut self, seed: u64, mode: Mode, lineup: Lineup, upgrades: &Upgrades) {
+        *self = Self::new();
+        self.players = usize::from(lineup.players);
+        self.full = self.data.extend_from_slice(MAGIC).is_err()
+            || self.data.push(VERSION).is_err()
+            || self.data.extend_from_slice(&seed.to_le_bytes()).is_err()
+            || self.data.push(mode.to_byte()).is_err()
+            || self.data.extend_from_slice(&lineup.to_bytes()).is_err()
+            || self.data.extend_from_slice(&upgrades.to_bytes()).is_err();
+    }
+
+    pub fn push(&mut self, input: Input) {
+        if self.full || self.data.is_empty() {
+            return;
+        }
+        let input = input.recorded(self.players);
+        if self.last == Some(input) {
+            if let Some(count) = self
+                .data
+                .get_mut(self.run)
+                .filter(|count| **count < u8::MAX)
+            {
+                *count += 1;
+                self.frames += 1;
+                return;
+            }
+        }
+        let run = self.data.len();
+        let mut bytes: Vec<u8, { MAX_PLAYERS + 6 }> = Vec::new();
+        bytes.push(1).ok();
+        bytes
+            .extend_from_slice(input.gamepads.get(..self.players).unwrap_or_default())
+            .ok();
+        bytes.push(input.mouse_buttons).ok();
+        if input.mouse_buttons != 0 {
+            bytes.extend_from_slice(&input.mouse.0.to_le_bytes()).ok();
+            bytes.extend_from_slice(&input.mouse.1.to_le_bytes()).ok();
+        }
+        if self.data.extend_from_slice(&bytes).is_err() {
+            self.full = true;
+            return;
+        }
+        self.run = run;
+        self.last = Some(input);
+        self.frames += 1;
+    }
+
+    /// Frames recorded so far.
+    pub const fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub const fn is_full(&self) -> bool {
+        self.full
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+pub enum ReplayError {
+    BadHeader,
+    Truncated,
+}
+
+impl ReplayError {
+    pub const fn message(&self) -> &'static str {
+        match *self {
+            Self::BadHeader => "replay: not a replay or wrong version",
+            Self::Truncated => "replay: data ends in the middle of a run",
+        }
+    }
+}
+
+/// Hands recorded input back one frame at a time.
+#[derive(Clone)]
+pub struct Playback<'a> {
+    data: &'a [u8],
+    seed: u64,
+    mode: Mode,
+    lineup: Lineup,
+    upgrades: Upgrades,
+    input: Input,
+    left: u8,
+}
+
+impl<'a> Playback<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, ReplayError> {
+        let header_size = match data.get(MAGIC.len()) {
+            Some(&SOLO_VERSION) => SOLO_HEADER_SIZE,
+            Some(&LINEUP_VERSION) => LINEUP_HEADER_SIZE,
+            _ => HEADER_SIZE,
+        };
+        let (header, runs) = data
+            .split_at_checked(header_size)
+            .ok_or(ReplayError::BadHeader)?;
+        let (magic, rest) = header.split_at(MAGIC.len());
+        let (&version, rest) = rest.split_first().ok_or(ReplayError::BadHeader)?;
+        let (seed, rest) = rest.split_at_checked(8).ok_or(ReplayError::BadHeader)?;
+        let (&mode, rest) = rest.split_first().ok_or(ReplayError::BadHeader)?;
+        if magic != MAGIC || ![VERSION, LINEUP_VERSION, SOLO_VERSION].contains(&version) {
+            return Err(ReplayError::BadHeader);
+        }
+        let (lineup, upgrades) = match *rest {
+            [] => (Some(Lineup::SOLO), Some(Upgrades::NONE)),
+            [players, teams] => (Lineup::from_bytes([players, teams]), Some(Upgrades::NONE)),
+            [players, teams, ref upgrades @ ..] => (
+                Lineup::from_bytes([players, teams]),
+                upgrades.try_into().ok().map(Upgrades::from_bytes),
+            ),
+            _ => (None, None),
+        };
+        let seed = seed.try_into().map_err(|_| ReplayError::BadHeader)?;
+        let playback = Self {
+            data: runs,
+            seed: u64::from_le_bytes(seed),
+            mode: Mode::from_byte(mode).ok_or(ReplayError::BadHeader)?,
+            lineup: lineup.ok_or(ReplayError::BadHeader)?,
+            upgrades: upgrades.ok_or(ReplayError::BadHeader)?,
+            input: Input::default(),
+            left: 0,
+        };
+        // Walk the runs once so `next` never meets a broken one.
+        let mut check = playback.clone();
+        while check.next_run()? {}
+        Ok(playback)
+    }
+
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub const fn lineup(&self) -> Lineup {
+        self.lineup
+    }
+
+    /// What the ships had bought when the run was recorded.
+    pub const fn upgrades(&self) -> &Upgrades {
+        &self.upgrades
+    }
+
+    fn next_run(&mut self) -> Result<bool, ReplayError> {
+        let Some((&count, rest)) = self.data.split_first() else {
+            return Ok(false);
+        };
+        let (recorded, rest) = rest
+            .split_at_checked(self.lineup.players.into())
+            .ok_or(ReplayError::Truncated)?;
+        let mut gamepads = [0; MAX_PLAYERS];
+        for (gamepad, &recorded) in gamepads.iter_mut().zip(recorded) {
+            *gamepad = recorded;
+        }
+        let [mouse_buttons, ref rest @ ..] = *rest else {
+            return Err(ReplayError::Truncated);
+        };
+        let (mouse, rest) = if mouse_buttons == 0 {
+            ((0, 0), rest)
+        } else {
+            let [x0, x1, y0, y1, ref rest @ ..] = *rest else {
+                return Err(ReplayError::Truncated);
+            };
+            (
+                (i16::from_le_bytes([x0, x1]), i16::from_le_bytes([y0, y1])),
+                rest,
+            )
+        };
+        self.data = rest;
+        self.input = Input {
+            gamepads,
+            mouse,
+            mouse_buttons,
+        };
+        self.left = count;
+        Ok(true)
+    }
+}
+
+impl Iterator for Playback<'_> {
+    type Item = Input;
+
+    fn next(&mut self) -> Option<Input> {
+        while self.left == 0 {
+            if !self.next_run().ok()? {
+                return None;
+            }
+        }
+        self.left -= 1;
+        Some(self.input)
+    }
+}
+
+/// Prints `data` through `trace` as hex, 32 bytes a line, for pasting into a file.
+pub fn trace_hex(prefix: &str, data: &[u8]) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    for chunk in data.chunks(32) {
+        let mut line: Vec<u8, 80> = Vec::new();
+        line.extend_from_slice(prefix.as_bytes()).ok();
+        for byte in chunk {
+            let high = DIGITS.get(usize::from(byte >> 4)).copied().unwrap_or(b'0');
+            let low = DIGITS.get(usize::from(byte & 0xf)).copied().unwrap_or(b'0');
+            line.extend_from_slice(&[high, low]).ok();
+        }
+        wasm4::trace(core::str::from_utf8(&line).unwrap_or_default());
+    }
+}
+
+/// Frame count and world checksum, which `tools` checks a replay against.
+pub fn trace_end(frames: u32, checksum: u32) {
+    let mut end = [0; 8];
+    end[..4].copy_from_slice(&frames.to_be_bytes());
+    end[4..].copy_from_slice(&checksum.to_be_bytes());
+    trace_hex("replay end: ", &end);
+}
@@ -5,7 +5,6 @@
     clippy::unwrap_used,
     clippy::unwrap_in_result,
     clippy::unneeded_field_pattern,
-    clippy::string_to_string,
     clippy::string_slice,
     clippy::string_add,
     clippy::str_to_string,
@@ -33,106 +32,68 @@
 )]
 #![allow(clippy::suboptimal_flops)]
 mod arrangement;
-use arrangement::{Arrangement, Channel, Sequence, Wave};
 mod common;
+mod composer;
+mod fixed;
+mod game;
+mod geometry;
 mod gfx;
+mod ghost;
+mod grid;
+mod input;
+mod math;
+mod menu;
+mod mode;
+mod palette;
 mod particle;
+mod planet;
 mod player;
+mod ring;
+mod save;
+mod scalar;
+mod scene;
+mod settings;
+mod sfx;
+mod song;
+mod station;
+mod theory;
+mod upgrade;
 mod wasm4;
 mod world;
 
 #[cfg(feature = "buddy-alloc")]
 mod alloc;
 
-use common::Vec2;
-use world::World;
+use game::Game;
+use input::Input;
 
-const CURSOR_SIZE: u8 = 4;
-const MOUSE_CURSOR: [u8; 2] = [0b1110_1000, 0b1010_0001];
+static mut GAME: Game = Game::new();
 
-static mut FRAME_COUNT: u32 = 0;
-const FRAMES_PER_TICK: u16 = 7;
-static mut WORLD: World = World::new();
-static mut MUSIC: Arrangement = Arrangement::new(
-    None,
-    Some(Channel::new(Wave::Pulse1)),
-    Some(Channel::new(Wave::Pulse2)),
-    Some(Channel::new(Wave::Noise)),
-);
+/// The one way to the game's state. WASM-4 calls `start` and then `update` one at a time on a
+/// single thread, and nothing else touches `GAME`, so this is never borrowed twice.
+fn game() -> &'static mut Game {
+    unsafe { &mut *core::ptr::addr_of_mut!(GAME) }
+}
 
 #[no_mangle]
-fn start() {
-    let mut random = unsafe { oorandom::Rand32::new(FRAME_COUNT.into()) };
-    let radius = random.rand_float() * 10.0 + 5.0;
-    unsafe {
-        *wasm4::PALETTE = [0x002d_162c, 0x0041_2752, 0x0068_3a68, 0x0097_75a6];
-        *wasm4::DRAW_COLORS = 0x4321;
-    }
+extern "C" fn start() {
+    game().start();
 }
 
 #[no_mangle]
-fn update() {
-    let time = unsafe { f64::from(FRAME_COUNT) / 60. };
-    let frame = unsafe { FRAME_COUNT };
-    let mut random = unsafe { oorandom::Rand32::new(FRAME_COUNT.into()) };
-    let gamepad = unsafe { *wasm4::GAMEPAD1 };
-    let mouse = unsafe { (*wasm4::MOUSE_X, *wasm4::MOUSE_Y) };
-    let mouse_pressed = unsafe { *wasm4::MOUSE_BUTTONS & wasm4::MOUSE_LEFT };
-    let music = unsafe { &mut MUSIC };
-
-    music.try_add_pattern(Wave::Pulse1, Sequence::gen_pattern(0, random.rand_float()));
-    if frame % u32::from(FRAMES_PER_TICK) == 0 {
-        music.update(1);
-    }
-
-    if mouse_pressed == 0 {
-        unsafe {
-            WORLD.mouse_clicked = false;
-        }
-    } else {
-        unsafe {
-            WORLD.mouse_click(mouse);
-        }
-        music.try_add_pattern(Wave::Noise, Sequence::gen_pattern(10, random.rand_float()));
-    }
-
-    unsafe {
-        WORLD.update(time, gamepad, music);
-    }
-
-    unsafe {
-        WORLD.draw();
-    }
-
-    unsafe {
-        FRAME_COUNT += 1;
-    }
-
-    {
-        unsafe {
-            *wasm4::DRAW_COLORS = 3;
-        }
-        let x: i32 = mouse.0.into();
-        let y: i32 = mouse.1.into();
-        wasm4::blit(
-            &MOUSE_CURSOR,
-            x,
-            y,
-            CURSOR_SIZE.into(),
-            CURSOR_SIZE.into(),
-            wasm4::BLIT_1BPP,
-        );
-    }
+extern "C" fn update() {
+    let game = game();
+    let live = Input::read();
+    game.update(live);
+    game.draw(live);
 }
 
 use core::panic::PanicInfo;
 
 #[panic_handler]
-fn panic(panic_info: &PanicInfo) -> ! {
-    if let Some(s) = panic_info.payload().downcast_ref::<&str>() {
-        wasm4::trace(s);
-    } else {
-        wasm4::trace("panic occurred");
-    }
+fn panic(_: &PanicInfo) -> ! {
+    // The message and where it came from take formatting code and strings that the cart has
+    // no room for.
+    wasm4::trace("panic occurred");
     loop {}
 }
@@ -0,0 +1,156 @@
+// `core` has no `sqrt`, `sin` and the like without `std`, so they are written out here.
+//
+// Each function is accurate to a few units in the last place over the ranges the game uses,
+// see `tools/src/bin/matherr.rs` for the measured errors. Building with the `fast-math`
+// feature swaps in cheap approximations instead: smaller and faster, but off by up to a few
+// percent, which is noticeable in orbits. Replays only play back on a build with the same
+// choice.
+
+// Exact comparisons are deliberate here: they pick out special values, not near ones.
+#![allow(
+    clippy::unreadable_literal,
+    clippy::excessive_precision,
+    clippy::float_cmp
+)]
+
+#[cfg(feature = "fast-math")]
+use core::f32::consts::FRAC_PI_2;
+#[cfg(not(feature = "fast-math"))]
+use core::f32::consts::PI;
+
+// π/2 split into parts with enough trailing zeros that multiplying one by a small
+// whole number is exact.
+#[cfg(not(feature = "fast-math"))]
+const FRAC_PI_2_HIGH: f32 = 1.570_312_5;
+#[cfg(not(feature = "fast-math"))]
+const FRAC_PI_2_MIDDLE: f32 = 4.837_513e-4;
+#[cfg(not(feature = "fast-math"))]
+const FRAC_PI_2_LOW: f32 = 7.549_79e-8;
+
+/// The largest whole number not above `value`; exact for every `f32`.
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss
+)]
+pub fn floor(value: f32) -> f32 {
+    // From 2^23 up every `f32` is already whole, and too big for an `i32` further on.
+    if value.is_nan() || value.abs() >= 8_388_608.0 {
+        return value;
+    }
+    let truncated = (value as i32) as f32;
+    if value < truncated {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+/// The nearest whole number, halves away from zero.
+#[cfg(not(feature = "fast-math"))]
+pub fn round(value: f32) -> f32 {
+    floor(value.abs() + 0.5).copysign(value)
+}
+
+#[cfg(not(feature = "fast-math"))]
+#[cfg_attr(feature = "fixed-point", allow(dead_code))]
+pub fn sqrt(value: f32) -> f32 {
+    if value.is_nan() || value < 0.0 {
+        return f32::NAN;
+    }
+    if value == 0.0 || value.is_infinite() {
+        return value;
+    }
+    // Halving the exponent is within 6%, and each Newton step squares the error.
+    let mut root = f32::from_bits((value.to_bits() + 0x3f80_0000) >> 1);
+    for _ in 0..3 {
+        root = 0.5 * (root + value / root);
+    }
+    root
+}
+
+#[cfg(feature = "fast-math")]
+#[cfg_attr(feature = "fixed-point", allow(dead_code))]
+pub fn sqrt(value: f32) -> f32 {
+    if value > 0.0 {
+        f32::from_bits((value.to_bits() + 0x3f80_0000) >> 1)
+    } else if value == 0.0 {
+        value
+    } else {
+        f32::NAN
+    }
+}
+
+// Polynomials for sine and cosine between -π/4 and π/4, from musl's `__sindf` and `__cosdf`.
+#[cfg(not(feature = "fast-math"))]
+fn sin_kernel(x: f32) -> f32 {
+    let z = x * x;
+    x + x * z * (-0.166_666_67 + z * (0.008_333_329 + z * (-0.000_198_393_35 + z * 2.718_311_5e-6)))
+}
+
+#[cfg(not(feature = "fast-math"))]
+fn cos_kernel(x: f32) -> f32 {
+    let z = x * x;
+    1.0 + z * (-0.5 + z * (0.041_666_623 + z * (-0.001_388_676_4 + z * 2.439_045e-5)))
+}
+
+/// `value` less the nearest multiple of π/2, and which multiple that was, modulo 4.
+/// Accurate while `value` is within a few thousand turns of zero.
+#[cfg(not(feature = "fast-math"))]
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+fn reduce_quarter_turns(value: f32) -> (f32, i32) {
+    let quarter_turns = round(value * (2.0 / PI));
+    let reduced = value
+        - quarter_turns * FRAC_PI_2_HIGH
+        - quarter_turns * FRAC_PI_2_MIDDLE
+        - quarter_turns * FRAC_PI_2_LOW;
+    (reduced, (quarter_turns % 4.0) as i32)
+}
+
+#[cfg(not(feature = "fast-math"))]
+#[cfg_attr(feature = "fixed-point", allow(dead_code))]
+pub fn sin(value: f32) -> f32 {
+    if !value.is_finite() {
+        return f32::NAN;
+    }
+    let (x, quadrant) = reduce_quarter_turns(value);
+    match quadrant.rem_euclid(4) {
+        0 => sin_kernel(x),
+        1 => cos_kernel(x),
+        2 => -sin_kernel(x),
+        _ => -cos_kernel(x),
+    }
+}
+
+#[cfg(not(feature = "fast-math"))]
+#[cfg_attr(feature = "fixed-point", allow(dead_code))]
+pub fn cos(value: f32) -> f32 {
+    if !value.is_finite() {
+        return f32::NAN;
+    }
+    let (x, quadrant) = reduce_quarter_turns(value);
+    match quadrant.rem_euclid(4) {
+        0 => cos_kernel(x),
+        1 => -sin_kernel(x),
+        2 => -cos_kernel(x),
+        _ => sin_kernel(x),
+    }
+}
+
+/// A parabola fitted to each half wave, within 0.001.
+#[cfg(feature = "fast-math")]
+#[cfg_attr(feature = "fixed-point", allow(dead_code))]
+pub fn cos(value: f32) -> f32 {
+    let mut x = value;
+    x *= core::f32::consts::FRAC_1_PI / 2.0;
+    x -= 0.25 + floor(x + 0.25);
+    x *= 16.0 * (x.abs() - 0.5);
+    x += 0.225 * x * (x.abs() - 1.0);
+    x
+}
+
+#[cfg(feature = "fast-math")]
+#[cfg_attr(feature = "fixed-point", allow(dead_code))]
+pub fn sin(value: f32) -> f32 {
+    cos(value - FRAC_PI_2)
+}
@@ -0,0 +1,57 @@
+use crate::sfx::{Sfx, Sound};
+use crate::wasm4;
+
+const ROW_HEIGHT: i32 = 12;
+
+/// A list picked from with up, down and X.
+pub struct Menu {
+    selected: usize,
+}
+
+impl Menu {
+    pub const fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    pub const fn at(selected: usize) -> Self {
+        Self { selected }
+    }
+
+    pub const fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Moves the selection on a fresh press; returns the item picked.
+    pub fn update(&mut self, pressed: u8, count: usize, sfx: &mut Sfx) -> Option<usize> {
+        if count == 0 {
+            return None;
+        }
+        if pressed & wasm4::BUTTON_UP != 0 {
+            self.selected = (self.selected + count - 1) % count;
+            sfx.play(Sound::Ui);
+        } else if pressed & wasm4::BUTTON_DOWN != 0 {
+            self.selected = (self.selected + 1) % count;
+            sfx.play(Sound::Ui);
+        } else if pressed & wasm4::BUTTON_1 != 0 {
+            sfx.play(Sound::Pickup);
+            return Some(self.selected);
+        }
+        None
+    }
+
+    /// One item a row from `y` down, the selected one bright and marked.
+    pub fn draw<T: AsRef<str>>(&self, items: &[T], y: i32) {
+        let mut row = y;
+        for (index, item) in items.iter().enumerate() {
+            let selected = index == self.selected;
+            wasm4::DrawColors::new()
+                .first(if selected { 4 } else { 2 })
+                .set();
+            if selected {
+                wasm4::text(">", 24, row);
+            }
+            wasm4::text(item, 40, row);
+            row += ROW_HEIGHT;
+        }
+    }
+}
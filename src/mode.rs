@@ -0,0 +1,212 @@
+use crate::player::MAX_PLAYERS;
+use heapless::String;
+
+/// Packages to deliver in a time trial.
+pub const TIME_TRIAL_DELIVERIES: u16 = 5;
+/// Targets in a race; each goes to whoever reaches it first.
+pub const RACE_LEGS: u16 = 5;
+/// Frames of thrust a survival run starts with; a delivery tops up half of it.
+pub const FUEL: u16 = 60 * 20;
+/// A boost burns as much as a second of thrust.
+pub const BOOST_FUEL: u16 = 60;
+
+/// The rules a run is played by, picked from the menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Explore,
+    TimeTrial,
+    Survival,
+    Zen,
+    Race,
+}
+
+impl Mode {
+    pub const ALL: [Self; 5] = [
+        Self::Explore,
+        Self::TimeTrial,
+        Self::Survival,
+        Self::Zen,
+        Self::Race,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Explore => "EXPLORE",
+            Self::TimeTrial => "TIME TRIAL",
+            Self::Survival => "SURVIVAL",
+            Self::Zen => "ZEN",
+            Self::Race => "RACE",
+        }
+    }
+
+    /// Two lines of at most 20 characters, the width of the screen.
+    pub const fn description(self) -> [&'static str; 2] {
+        match self {
+            Self::Explore => ["Deliver at your", "own pace."],
+            Self::TimeTrial => ["Deliver 5 packages", "as fast as you can."],
+            Self::Survival => ["Fuel runs out, and", "gravity grows."],
+            Self::Zen => ["No packages, just", "space."],
+            Self::Race => ["First to each of 5", "targets takes it."],
+        }
+    }
+
+    pub const fn has_targets(self) -> bool {
+        !matches!(self, Self::Zen)
+    }
+
+    /// Fuel a run starts with; `None` is a tank that never empties.
+    pub const fn fuel(self) -> Option<u16> {
+        match self {
+            Self::Survival => Some(FUEL),
+            Self::Explore | Self::TimeTrial | Self::Zen | Self::Race => None,
+        }
+    }
+
+    /// How much stronger than normal planets pull after `seconds` of play.
+    pub fn gravity(self, seconds: u32) -> f32 {
+        match self {
+            // Twice as strong after two minutes, and on from there.
+            Self::Survival => 1.0 + f32::from(u16::try_from(seconds).unwrap_or(u16::MAX)) / 120.0,
+            Self::Explore | Self::TimeTrial | Self::Zen | Self::Race => 1.0,
+        }
+    }
+
+    pub const fn to_byte(self) -> u8 {
+        match self {
+            Self::Explore => 0,
+            Self::TimeTrial => 1,
+            Self::Survival => 2,
+            Self::Zen => 3,
+            Self::Race => 4,
+        }
+    }
+
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Explore),
+            1 => Some(Self::TimeTrial),
+            2 => Some(Self::Survival),
+            3 => Some(Self::Zen),
+            4 => Some(Self::Race),
+            _ => None,
+        }
+    }
+}
+
+/// Whether ships with more than one player work together or against each other.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Teams {
+    /// Every delivery counts for everyone.
+    CoOp,
+    /// The same targets for all, and a point to whoever gets there first.
+    Versus,
+}
+
+/// Who flies a run, picked on the briefing screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Lineup {
+    /// 1 to `MAX_PLAYERS`; ship `n` flies on gamepad `n + 1`.
+    pub players: u8,
+    pub teams: Teams,
+    /// Whether ships bounce off each other.
+    pub bumps: bool,
+}
+
+impl Lineup {
+    pub const SOLO: Self = Self {
+        players: 1,
+        teams: Teams::CoOp,
+        bumps: false,
+    };
+
+    /// Whether ships play against each other in `mode`; a race always is.
+    pub const fn is_versus(self, mode: Mode) -> bool {
+        self.players > 1 && (matches!(self.teams, Teams::Versus) || matches!(mode, Mode::Race))
+    }
+
+    /// The player count, then flags: 1 for versus, 2 for bumps.
+    pub const fn to_bytes(self) -> [u8; 2] {
+        let versus = match self.teams {
+            Teams::CoOp => 0,
+            Teams::Versus => 1,
+        };
+        let bumps = if self.bumps { 2 } else { 0 };
+        [self.players, versus | bumps]
+    }
+
+    pub fn from_bytes([players, flags]: [u8; 2]) -> Option<Self> {
+        if flags & !0b11 != 0 || !(1..=MAX_PLAYERS).contains(&usize::from(players)) {
+            return None;
+        }
+        Some(Self {
+            players,
+            teams: if flags & 1 == 0 {
+                Teams::CoOp
+            } else {
+                Teams::Versus
+            },
+            bumps: flags & 2 != 0,
+        })
+    }
+}
+
+/// How a run that can end ended.
+#[derive(Clone, Copy)]
+pub enum Outcome {
+    /// Every package delivered, after this many frames.
+    TimeTrial { frames: u32 },
+    /// Out of fuel after this many deliveries.
+    Survival { deliveries: u16 },
+    /// A versus run or race, won by the ship with this index or tied.
+    Versus { winner: Option<u8> },
+}
+
+impl Outcome {
+    pub fn describe(self) -> String<20> {
+        let mut text = String::new();
+        match self {
+            Self::TimeTrial { frames } => {
+                let tenths = frames.div_euclid(6);
+                push_number(&mut text, tenths.div_euclid(10));
+                text.push('.').ok();
+                push_number(&mut text, tenths % 10);
+                text.push_str(" SECONDS").ok();
+            }
+            Self::Survival { deliveries } => {
+                push_number(&mut text, deliveries.into());
+                text.push_str(" DELIVERED").ok();
+            }
+            Self::Versus {
+                winner: Some(winner),
+            } => {
+                text.push_str("PLAYER ").ok();
+                push_number(&mut text, u32::from(winner) + 1);
+                text.push_str(" WINS").ok();
+            }
+            Self::Versus { winner: None } => {
+                text.push_str("A DRAW").ok();
+            }
+        }
+        text
+    }
+}
+
+/// Decimal digits without pulling in `core::fmt`.
+pub fn push_number<const N: usize>(text: &mut String<N>, value: u32) {
+    let mut digits = [b'0'; 10];
+    let mut start = digits.len();
+    let mut value = value;
+    loop {
+        start -= 1;
+        if let (Some(digit), Ok(last)) = (digits.get_mut(start), u8::try_from(value % 10)) {
+            *digit = b'0' + last;
+        }
+        value = value.div_euclid(10);
+        if value == 0 || start == 0 {
+            break;
+        }
+    }
+    let digits = digits.get(start..).unwrap_or_default();
+    text.push_str(core::str::from_utf8(digits).unwrap_or_default())
+        .ok();
+}
@@ -0,0 +1,102 @@
+use crate::wasm4;
+
+/// Space is split into squares this wide, each with its own tint.
+pub const SECTOR_SIZE: f32 = 1_000.0;
+/// Added to red, green and blue in a sector; the first is home.
+const SECTOR_TINTS: [[i16; 3]; 4] = [[0, 0, 0], [-12, 6, 28], [26, 8, -14], [-10, 22, 2]];
+
+/// Four colours, darkest first, as WASM-4 expects them.
+pub struct Theme {
+    pub name: &'static str,
+    pub colors: [u32; 4],
+    /// Whether sectors may tint it; the accessible themes keep their colours exact.
+    pub tinted: bool,
+}
+
+pub const THEMES: [Theme; 5] = [
+    Theme {
+        name: "DUSK",
+        colors: [0x002d_162c, 0x0041_2752, 0x0068_3a68, 0x0097_75a6],
+        tinted: true,
+    },
+    Theme {
+        name: "DEEP",
+        colors: [0x000f_1a2b, 0x0024_3b5c, 0x0047_7aa1, 0x00a8_d8e8],
+        tinted: true,
+    },
+    Theme {
+        name: "EMBER",
+        colors: [0x001f_0e0c, 0x0052_2318, 0x00a1_4a26, 0x00f2_b36b],
+        tinted: true,
+    },
+    // Black to white with even steps in between.
+    Theme {
+        name: "CONTRAST",
+        colors: [0x0000_0000, 0x0055_5555, 0x00aa_aaaa, 0x00ff_ffff],
+        tinted: false,
+    },
+    // Blue and orange from the Okabe-Ito set, told apart by all common colour blindness,
+    // and still in order of brightness for the dithering.
+    Theme {
+        name: "SAFE",
+        colors: [0x0010_1830, 0x0000_72b2, 0x00e6_9f00, 0x00f0_e442],
+        tinted: false,
+    },
+];
+
+/// Falls back to the first theme for an index from a newer save.
+pub fn theme(index: u8) -> &'static Theme {
+    THEMES.get(usize::from(index)).unwrap_or(&THEMES[0])
+}
+
+/// The tint of the sector at `sector`, picked so neighbours differ.
+fn sector_tint((x, y): (i32, i32)) -> [i16; 3] {
+    let index = x
+        .wrapping_mul(3)
+        .wrapping_add(y.wrapping_mul(2))
+        .rem_euclid(4);
+    let index = usize::try_from(index).unwrap_or(0);
+    SECTOR_TINTS.get(index).copied().unwrap_or_default()
+}
+
+/// Owns the WASM-4 palette: the chosen theme, faded and tinted.
+pub struct Palette {
+    tint: [i16; 3],
+    written: Option<[u32; 4]>,
+}
+
+impl Palette {
+    pub const fn new() -> Self {
+        Self {
+            tint: [0; 3],
+            written: None,
+        }
+    }
+
+    /// Call every frame. `brightness` is 0 for black to 255 for the theme as it is;
+    /// `sector` is where the ship is, or `None` away from space. The tint drifts a step
+    /// a frame, so crossing into a sector shifts the colours gently.
+    pub fn update(&mut self, theme: &Theme, brightness: u8, sector: Option<(i32, i32)>) {
+        let target = match sector {
+            Some(sector) if theme.tinted => sector_tint(sector),
+            _ => [0; 3],
+        };
+        for (tint, target) in self.tint.iter_mut().zip(target) {
+            *tint += (target - *tint).signum();
+        }
+        let colors = theme.colors.map(|color| {
+            let channel = |shift: u32, tint: i16| {
+                let value = i32::try_from((color >> shift) & 0xff).unwrap_or(0);
+                let value = (value + i32::from(tint)).clamp(0, 255);
+                let value = (value * i32::from(brightness)).div_euclid(255);
+                u32::try_from(value).unwrap_or(0) << shift
+            };
+            let [red, green, blue] = self.tint;
+            channel(16, red) | channel(8, green) | channel(0, blue)
+        });
+        if self.written != Some(colors) {
+            wasm4::set_palette(colors);
+            self.written = Some(colors);
+        }
+    }
+}
@@ -1,5 +1,8 @@
-use crate::common::{map, Rect, Vec2};
-use crate::gfx::draw_pixel;
+use crate::common::map;
+use crate::geometry::{Circle, Rect, Vec2};
+use crate::gfx::draw_disc;
+use crate::scalar::{scalar, Scalar};
+use crate::wasm4::Framebuffer;
 
 pub const MAX_LIFETIME: u16 = 600;
 pub struct PhysicsObject {
@@ -13,17 +16,16 @@ impl PhysicsObject {
     pub const fn new(pos: Vec2, lifetime: Option<u16>) -> Self {
         Self {
             pos,
-            vel: Vec2::new(0.0, 0.0),
-            acc: Vec2::new(0.0, 0.0),
+            vel: Vec2::ZERO,
+            acc: Vec2::ZERO,
             lifetime,
         }
     }
 
     pub fn update(&mut self) {
         self.vel += self.acc;
-        //self.vel = self.vel * 0.99;
         self.pos += self.vel;
-        self.acc = Vec2::new(0.0, 0.0);
+        self.acc = Vec2::ZERO;
         if let Some(lifetime) = self.lifetime {
             match lifetime.checked_sub(1) {
                 Some(lifetime) => self.lifetime = Some(lifetime),
@@ -32,37 +34,41 @@ impl PhysicsObject {
         }
     }
 
+    /// Puts it at `pos` moving at `vel`, dropping any force applied since the last update.
+    pub const fn hold(&mut self, pos: Vec2, vel: Vec2) {
+        self.pos = pos;
+        self.vel = vel;
+        self.acc = Vec2::ZERO;
+    }
+
+    /// Slows it by `fraction` of its speed, as air does.
+    pub fn drag(&mut self, fraction: Scalar) {
+        self.vel *= scalar(1.0) - fraction;
+    }
+
     pub fn apply_force(&mut self, force: Vec2) {
         self.acc += force;
     }
 
-    pub fn debug_draw(&self, view: &Rect) {
-        let mut color = 255u8;
-        if let Some(lifetime) = self.lifetime {
-            color = map(
+    #[allow(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn debug_draw(&self, view: &Rect, screen: &mut Framebuffer) {
+        let color = self.lifetime.map_or(255, |lifetime| {
+            map(
                 f32::from(lifetime),
                 0.0,
                 f32::from(MAX_LIFETIME),
                 0.0,
                 255.0,
-            ) as u8;
-        };
+            ) as u8
+        });
         if color != 0 {
-            let left = view.top_left.x;
-            let top = view.top_left.y;
-
-            for screen_y in 0..160u8 {
-                for screen_x in 0..160u8 {
-                    let screen_point =
-                        Vec2::new(f32::from(screen_x) + left, f32::from(screen_y) + top);
-                    let distance = (self.pos).distance(screen_point);
-                    if distance > 1.0 {
-                        continue;
-                    }
-
-                    draw_pixel(screen_x, screen_y, color);
-                }
-            }
+            draw_disc(screen, view, &Circle::new(self.pos, scalar(1.0)), &|_| {
+                color
+            });
         }
     }
 }
@@ -0,0 +1,162 @@
+// Planets, and the kinds they come in. The kind of a planet follows from where it is and the
+// world seed, like a station does, so a replay finds the same kinds without any random numbers
+// taken from the frame. Only rocky, icy and lava planets have a surface to deliver to.
+
+use crate::geometry::{Circle, Rect, Vec2};
+use crate::gfx::draw_disc;
+use crate::scalar::{over_square, scalar, to_f32, Scalar};
+use crate::wasm4::{self, Framebuffer};
+
+// Air reaches this far out from the surface, as a fraction of the radius, thinning out the
+// higher up it is.
+const ATMOSPHERE: f32 = 0.5;
+
+/// What a planet is, which decides how hard it pulls, its air, its look and whether
+/// packages go there.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Rocky,
+    /// Light for its size, in thick air.
+    GasGiant,
+    Ice,
+    Lava,
+    /// Small and black, pulling harder than anything.
+    BlackHole,
+    /// Smaller still and bright, nearly as heavy.
+    NeutronStar,
+}
+
+impl Kind {
+    /// Picked from the planet's place and the world seed; half of all planets are rocky.
+    fn at(pos: Vec2, seed: u64) -> Self {
+        // Turned over, so it does not follow the numbers a station there takes.
+        let place = u64::from(pos.x.to_bits()) << 32 | u64::from(pos.y.to_bits());
+        match oorandom::Rand32::new(!(seed ^ place)).rand_range(0..16) {
+            0..=7 => Self::Rocky,
+            8..=9 => Self::GasGiant,
+            10..=11 => Self::Ice,
+            12..=13 => Self::Lava,
+            14 => Self::BlackHole,
+            _ => Self::NeutronStar,
+        }
+    }
+
+    // The radius it is generated with is shrunk by this much.
+    const fn size(self) -> f32 {
+        match self {
+            Self::BlackHole => 0.4,
+            Self::NeutronStar => 0.25,
+            Self::Rocky | Self::GasGiant | Self::Ice | Self::Lava => 1.0,
+        }
+    }
+
+    // Pull for each pixel of radius, against a rocky planet's.
+    const fn density(self) -> f32 {
+        match self {
+            Self::Rocky => 1.0,
+            Self::GasGiant => 0.6,
+            Self::Ice => 0.8,
+            Self::Lava => 1.2,
+            Self::BlackHole => 5.0,
+            Self::NeutronStar => 6.0,
+        }
+    }
+
+    // Speed lost to the air each frame at the surface, 0 for none.
+    const fn air(self) -> Scalar {
+        scalar(match self {
+            Self::GasGiant => 0.05,
+            Self::Lava => 0.03,
+            Self::Ice => 0.02,
+            Self::Rocky | Self::BlackHole | Self::NeutronStar => 0.0,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Planet {
+    pub pos: Vec2,
+    pub radius: Scalar,
+    color: u8,
+    kind: Kind,
+}
+
+impl Planet {
+    /// A planet at `pos` of the kind the world `seed` puts there, `radius` big before the kind
+    /// shrinks it.
+    pub fn new(pos: Vec2, radius: Scalar, color: u8, seed: u64) -> Self {
+        let kind = Kind::at(pos, seed);
+        Self {
+            pos,
+            radius: radius * kind.size(),
+            color,
+            kind,
+        }
+    }
+
+    pub fn gravity(&self, distance: Scalar) -> Scalar {
+        let distance_to_surface = distance - (self.radius - 2.0);
+        over_square(self.radius * self.kind.density(), distance_to_surface)
+    }
+
+    /// The planet's surface, which is what ships crash into and deliver to.
+    pub const fn body(&self) -> Circle {
+        Circle::new(self.pos, self.radius)
+    }
+
+    /// Whether packages can be delivered here: not without a surface to land on.
+    pub const fn takes_deliveries(&self) -> bool {
+        matches!(self.kind, Kind::Rocky | Kind::Ice | Kind::Lava)
+    }
+
+    fn atmosphere(&self) -> Circle {
+        Circle::new(self.pos, self.radius * (1.0 + ATMOSPHERE))
+    }
+
+    /// How much of its speed something at `pos` loses to the air this frame, `None` outside it.
+    /// The air is as thick as it gets at the surface and thins away with the square of the
+    /// height, to nothing at the top.
+    pub fn drag(&self, pos: Vec2) -> Option<Scalar> {
+        let air = self.kind.air();
+        if air <= 0.0 {
+            return None;
+        }
+        let height = (pos.distance(self.pos) - self.radius) / (self.radius * ATMOSPHERE);
+        let thickness = (scalar(1.0) - height).min(scalar(1.0));
+        (thickness > 0.0).then_some(air * thickness * thickness)
+    }
+
+    #[allow(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn draw(&self, view: &Rect, screen: &mut Framebuffer) {
+        if !view.intersects(&self.atmosphere().bounds()) {
+            return;
+        }
+        if self.kind.air() > 0.0 {
+            let air = self.atmosphere();
+            let corner = air.center - view.top_left - Vec2::new(air.radius, air.radius);
+            let size = to_f32(air.radius) as u32 * 2;
+            wasm4::DrawColors::new().first(0).second(2).set();
+            wasm4::oval(to_f32(corner.x) as i32, to_f32(corner.y) as i32, size, size);
+        }
+        let (radius, color) = (to_f32(self.radius), f32::from(self.color));
+        draw_disc(screen, view, &self.body(), &|distance| {
+            // 0 in the middle to 1 at the surface.
+            let out = to_f32(distance) / radius;
+            (match self.kind {
+                Kind::Rocky => out * color,
+                Kind::GasGiant if ((out * 6.0) as u8).is_multiple_of(2) => color,
+                Kind::GasGiant => color * 0.5,
+                Kind::Ice => 255.0 - out * 80.0,
+                Kind::Lava if out > 0.8 => 255.0,
+                Kind::Lava => out * 90.0,
+                Kind::BlackHole if out > 0.8 => color,
+                Kind::BlackHole => 0.0,
+                Kind::NeutronStar => 255.0,
+            }) as u8
+        });
+    }
+}
@@ -1,15 +1,37 @@
-use crate::common::{abs, clamp, Rect, Vec2};
+use crate::common::clamp;
+use crate::geometry::{Rect, Vec2};
+use crate::gfx::line;
 use crate::particle::{PhysicsObject, MAX_LIFETIME};
+use crate::scalar::{scalar, Scalar};
+use crate::upgrade::{Upgrade, Upgrades};
 use crate::wasm4;
 
-use core::f32::consts::PI;
-
-const POWER: f32 = 0.02;
-const MAX_SPEED: f32 = 2.0;
+const POWER: Scalar = scalar(0.02);
+const MAX_SPEED: Scalar = scalar(2.0);
+/// Ships in one run, one per WASM-4 gamepad.
+pub const MAX_PLAYERS: usize = 4;
+/// Hull colour of each player's ship. There are only three colours besides the
+/// background, so the fourth ship is told apart by a keel in the second colour.
+const HULL_COLORS: [u8; MAX_PLAYERS] = [4, 3, 2, 4];
+const KEEL_COLOR: u8 = 2;
+// Heat gained for each bit of drag times the speed squared, and lost every frame. The hull
+// glows from `GLOW` on, and from `SCORCHING` the cargo inside suffers.
+const HEATING: f32 = 2.0;
+const COOLING: f32 = 0.01;
+const GLOW: f32 = 0.2;
+const SCORCHING: f32 = 0.6;
 
 pub struct Player {
     pub physics: PhysicsObject,
     target: Option<Vec2>,
+    // Extra thrust, as a fraction of `power`, for a ship that is falling behind.
+    catch_up: Scalar,
+    // `POWER` and `MAX_SPEED` with the upgrades, and how many times stronger the stabilisers are.
+    power: Scalar,
+    max_speed: Scalar,
+    stabiliser: Scalar,
+    // From 0 for cold to 1, from flying fast through air.
+    heat: Scalar,
 }
 
 impl Player {
@@ -17,27 +39,46 @@ impl Player {
         Self {
             physics: PhysicsObject::new(pos, Some(MAX_LIFETIME)),
             target: None,
+            catch_up: scalar(0.0),
+            power: POWER,
+            max_speed: MAX_SPEED,
+            stabiliser: scalar(1.0),
+            heat: scalar(0.0),
         }
     }
 
+    /// Makes the ship as good as `upgrades` say.
+    pub fn fit(&mut self, upgrades: &Upgrades) {
+        self.power = POWER * upgrades.factor(Upgrade::Engine);
+        self.max_speed = MAX_SPEED * upgrades.factor(Upgrade::TopSpeed);
+        self.stabiliser = upgrades.factor(Upgrade::Stabiliser);
+    }
+
+    /// Gives the arrows `catch_up` times more thrust than normal on top, 0 for none.
+    pub const fn set_catch_up(&mut self, catch_up: Scalar) {
+        self.catch_up = catch_up;
+    }
+
     pub fn handle_gamepad(&mut self, gamepad: u8) -> Option<Vec2> {
         if gamepad & wasm4::BUTTON_2 == 0 {
-            let mut force = Vec2::new(0.0, 0.0);
+            let zero = scalar(0.0);
+            let mut force = Vec2::ZERO;
             let speed = self.physics.vel;
-            let delta_x = MAX_SPEED - abs(speed.x);
-            let delta_y = MAX_SPEED - abs(speed.y);
+            let delta_x = self.max_speed - speed.x.abs();
+            let delta_y = self.max_speed - speed.y.abs();
             if gamepad & wasm4::BUTTON_UP != 0 {
-                force = Vec2::new(0.0, clamp(-delta_y, -POWER, 0.0));
+                force = Vec2::new(zero, clamp(-delta_y, -self.power, zero));
             } else if gamepad & wasm4::BUTTON_DOWN != 0 {
-                force = Vec2::new(0.0, clamp(delta_y, 0.0, POWER));
+                force = Vec2::new(zero, clamp(delta_y, zero, self.power));
             }
             if gamepad & wasm4::BUTTON_LEFT != 0 {
-                force += Vec2::new(clamp(-delta_x, -POWER, 0.0), 0.0);
+                force += Vec2::new(clamp(-delta_x, -self.power, zero), zero);
             } else if gamepad & wasm4::BUTTON_RIGHT != 0 {
-                force += Vec2::new(clamp(delta_x, 0.0, POWER), 0.0);
+                force += Vec2::new(clamp(delta_x, zero, self.power), zero);
             }
-            if gamepad & wasm4::BUTTON_1 != 0 && self.physics.vel.magnitude() < 0.5 {
-                force += Vec2::new(0.0, -MAX_SPEED);
+            force *= 1.0 + self.catch_up;
+            if gamepad & wasm4::BUTTON_1 != 0 && self.can_boost() {
+                force += Vec2::new(zero, -MAX_SPEED);
             }
             self.apply_force(force);
             if force.magnitude() > 0.005 {
@@ -50,15 +91,49 @@ impl Player {
         None
     }
 
+    /// Moves it by `by`, along with where it is holding still.
+    pub fn shift(&mut self, by: Vec2) {
+        self.physics.pos += by;
+        if let Some(target) = self.target.as_mut() {
+            *target += by;
+        }
+    }
+
+    /// Boosters only fire when the ship is nearly still, to get out of gravity wells.
+    pub fn can_boost(&self) -> bool {
+        self.physics.vel.magnitude() < 0.5
+    }
+
+    /// Heats the hull from the air slowing it by `drag` this frame, the more the faster it goes.
+    pub fn heat_up(&mut self, drag: Scalar) {
+        let speed = self.physics.vel.magnitude();
+        let heating = drag * HEATING;
+        self.heat = (self.heat + speed * speed * heating).min(scalar(1.0));
+    }
+
+    /// How hot the hull is, from 0 to 1.
+    pub const fn heat(&self) -> Scalar {
+        self.heat
+    }
+
+    /// Whether it is hot enough to damage the cargo.
+    pub fn is_scorching(&self) -> bool {
+        self.heat > SCORCHING
+    }
+
     pub fn update(&mut self) {
+        self.heat = (self.heat - COOLING).max(scalar(0.0));
         self.physics.update();
         if let Some(target) = self.target {
             self.apply_force(
-                ((target - self.physics.pos) - self.physics.vel).normalized() * POWER * 2.5,
+                ((target - self.physics.pos) - self.physics.vel).normalized()
+                    * POWER
+                    * 2.5
+                    * self.stabiliser,
             );
         }
-        if self.physics.vel.magnitude() > MAX_SPEED {
-            self.physics.vel = self.physics.vel.normalized() * MAX_SPEED;
+        if self.physics.vel.magnitude() > self.max_speed {
+            self.physics.vel = self.physics.vel.normalized() * self.max_speed;
         }
     }
 
@@ -66,14 +141,17 @@ impl Player {
         self.physics.apply_force(force);
     }
 
-    pub fn collide(&mut self, to_collider: Vec2) {
+    pub fn collide(&mut self) {
         self.apply_force(self.physics.vel * -0.2);
     }
 
-    #[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
-    pub fn draw2(&self, view: &Rect) {
-        let size = 3;
+    /// The colour that stands for player `index`, on the ship and in the HUD.
+    pub fn color(index: usize) -> u8 {
+        HULL_COLORS.get(index).copied().unwrap_or(4)
+    }
 
+    /// Draws the ship of player `index` in their colour.
+    pub fn draw2(&self, view: &Rect, index: usize) {
         let left = view.top_left.x;
         let top = view.top_left.y;
 
@@ -82,61 +160,25 @@ impl Player {
 
         let start = Vec2::new(x1, y1 - 3.0);
         let front = self.physics.vel * 1.5 + start;
-        let right = self.physics.vel.rotated(PI / 2.0) + start;
+        let right = self.physics.vel.perpendicular() + start;
         let back = self.physics.vel * -1.5 + start;
-        let left = self.physics.vel.rotated(-PI / 2.0) + start;
-        unsafe {
-            *wasm4::DRAW_COLORS = 4;
+        let left = -self.physics.vel.perpendicular() + start;
+        wasm4::DrawColors::new().first(Self::color(index)).set();
+        line(left, front);
+        line(right, front);
+        line(left, back);
+        line(right, back);
+        if index == MAX_PLAYERS - 1 {
+            wasm4::DrawColors::new().first(KEEL_COLOR).set();
+            line(front, back);
         }
-        wasm4::line(left.x as i32, left.y as i32, front.x as i32, front.y as i32);
-        wasm4::line(
-            right.x as i32,
-            right.y as i32,
-            front.x as i32,
-            front.y as i32,
-        );
-        wasm4::line(left.x as i32, left.y as i32, back.x as i32, back.y as i32);
-        wasm4::line(right.x as i32, right.y as i32, back.x as i32, back.y as i32);
-    }
-
-    #[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
-    pub fn draw(&self, view: &Rect) {
-        let size = 2;
-
-        let left = view.top_left.x;
-        let top = view.top_left.y;
-
-        let x1 = self.physics.pos.x - left;
-        let y1 = self.physics.pos.y - top;
-
-        let start = Vec2::new(x1, y1 - 2.0);
-        let end = self.physics.vel + start;
-        unsafe {
-            *wasm4::DRAW_COLORS = 4;
+        if self.heat > GLOW {
+            // Hot air piled up ahead of the nose, brightest once it scorches.
+            let bow = self.physics.vel * 2.5 + start;
+            let glow = if self.is_scorching() { 4 } else { 3 };
+            wasm4::DrawColors::new().first(glow).set();
+            line(left, bow);
+            line(right, bow);
         }
-        wasm4::line(
-            x1 as i32 - size,
-            y1 as i32 - size,
-            x1 as i32 + size,
-            y1 as i32 - size,
-        );
-        wasm4::line(
-            start.x as i32 - size,
-            start.y as i32,
-            end.x as i32,
-            end.y as i32,
-        );
-        wasm4::line(
-            start.x as i32 + size,
-            start.y as i32,
-            end.x as i32,
-            end.y as i32,
-        );
-        wasm4::line(
-            start.x as i32,
-            start.y as i32 + 2,
-            end.x as i32,
-            end.y as i32,
-        );
     }
 }
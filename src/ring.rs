@@ -0,0 +1,46 @@
+// The last so many things written, numbered the way heapless's `HistoryBuffer` numbers them,
+// but open to changes in place, which the heapless 0.7 one has no way to make. The world moves
+// everything in its rings by the same amount when it recentres.
+
+use heapless::Vec;
+
+pub struct Ring<T, const N: usize> {
+    items: Vec<T, N>,
+    // Where the next one goes, once the ring is full.
+    write_at: usize,
+}
+
+impl<T, const N: usize> Ring<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            write_at: 0,
+        }
+    }
+
+    // `Vec::clear` in heapless 0.7 indexes one past the end as it drops, which debug builds
+    // catch; popping empties it the same way.
+    pub fn clear(&mut self) {
+        while self.items.pop().is_some() {}
+        self.write_at = 0;
+    }
+
+    /// Adds `item`, in place of the oldest one once the ring is full.
+    pub fn write(&mut self, item: T) {
+        if let Err(item) = self.items.push(item) {
+            if let Some(oldest) = self.items.get_mut(self.write_at) {
+                *oldest = item;
+            }
+        }
+        self.write_at = (self.write_at + 1).rem_euclid(N);
+    }
+
+    /// Everything in the ring, by number rather than age.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+}
@@ -0,0 +1,149 @@
+use crate::ghost::{Route, Routes};
+use crate::settings::Settings;
+use crate::upgrade::Upgrades;
+use crate::wasm4;
+use heapless::Vec;
+
+// SAVE FORMAT, kept in the 1024 bytes WASM-4 gives a cart
+// "SDSV" | version | settings (6 bytes, see `Settings::to_bytes`)
+//     | upgrades (8 bytes, see `Upgrades::to_bytes`) | route count
+// per route: target x, y, start x, y (i16 little endian), frames from pickup to delivery
+//     (u16 little endian), step count, then per step x and y moved (i8)
+const MAGIC: &[u8] = b"SDSV";
+const VERSION: u8 = 3;
+// Version 2 had no upgrades, and version 1 no settings either.
+const VERSION_WITHOUT_UPGRADES: u8 = 2;
+const VERSION_WITHOUT_SETTINGS: u8 = 1;
+const DISK_SIZE: usize = 1024;
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let (&first, rest) = self.data.split_first()?;
+        self.data = rest;
+        Some(first)
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        Some(i16::from_le_bytes([self.byte()?, self.byte()?]))
+    }
+
+    fn settings(&mut self) -> Option<[u8; 6]> {
+        Some([
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+            self.byte()?,
+        ])
+    }
+
+    fn upgrades(&mut self) -> Option<[u8; 8]> {
+        let mut bytes = [0; 8];
+        for byte in &mut bytes {
+            *byte = self.byte()?;
+        }
+        Some(bytes)
+    }
+
+    fn route(&mut self) -> Option<Route> {
+        let target = (self.i16()?, self.i16()?);
+        let start = (self.i16()?, self.i16()?);
+        let frames = u16::from_le_bytes([self.byte()?, self.byte()?]);
+        let count = self.byte()?;
+        let mut steps = Vec::new();
+        for _ in 0..count {
+            let x = i8::from_le_bytes([self.byte()?]);
+            let y = i8::from_le_bytes([self.byte()?]);
+            steps.push((x, y)).ok()?;
+        }
+        Some(Route {
+            target,
+            start,
+            frames,
+            steps,
+        })
+    }
+}
+
+/// Everything kept between sessions.
+pub struct Save {
+    pub settings: Settings,
+    pub upgrades: Upgrades,
+    pub routes: Routes,
+}
+
+/// Nothing on disk, or a save from an unknown version, loads as the defaults;
+/// losing best times is not worth refusing to start.
+fn parse(data: &[u8]) -> Save {
+    let mut save = Save {
+        settings: Settings::new(),
+        upgrades: Upgrades::NONE,
+        routes: Routes::new(),
+    };
+    let Some((&version, rest)) = data.strip_prefix(MAGIC).and_then(<[u8]>::split_first) else {
+        return save;
+    };
+    let mut reader = Reader { data: rest };
+    match version {
+        VERSION | VERSION_WITHOUT_UPGRADES => {
+            let Some(bytes) = reader.settings() else {
+                return save;
+            };
+            save.settings = Settings::from_bytes(bytes);
+        }
+        VERSION_WITHOUT_SETTINGS => {}
+        _ => return save,
+    }
+    if version == VERSION {
+        // Cut short, there are no routes after them either.
+        save.upgrades = reader
+            .upgrades()
+            .map_or(Upgrades::NONE, Upgrades::from_bytes);
+    }
+    for _ in 0..reader.byte().unwrap_or(0) {
+        let Some(route) = reader.route() else {
+            save.routes = Routes::new();
+            return save;
+        };
+        save.routes.insert(route);
+    }
+    save
+}
+
+fn to_bytes(settings: &Settings, upgrades: &Upgrades, routes: &Routes) -> Vec<u8, DISK_SIZE> {
+    let mut data = Vec::new();
+    data.extend_from_slice(MAGIC).ok();
+    data.push(VERSION).ok();
+    data.extend_from_slice(&settings.to_bytes()).ok();
+    data.extend_from_slice(&upgrades.to_bytes()).ok();
+    data.push(u8::try_from(routes.iter().count()).unwrap_or(0))
+        .ok();
+    for route in routes.iter() {
+        for value in [route.target.0, route.target.1, route.start.0, route.start.1] {
+            data.extend_from_slice(&value.to_le_bytes()).ok();
+        }
+        data.extend_from_slice(&route.frames.to_le_bytes()).ok();
+        data.push(u8::try_from(route.steps.len()).unwrap_or(0)).ok();
+        for &(x, y) in &route.steps {
+            data.extend_from_slice(&x.to_le_bytes()).ok();
+            data.extend_from_slice(&y.to_le_bytes()).ok();
+        }
+    }
+    data
+}
+
+pub fn load() -> Save {
+    let mut data = [0; DISK_SIZE];
+    let read = wasm4::diskr(&mut data);
+    parse(data.get(..read.try_into().unwrap_or(0)).unwrap_or_default())
+}
+
+pub fn store(settings: &Settings, upgrades: &Upgrades, routes: &Routes) {
+    let data = to_bytes(settings, upgrades, routes);
+    wasm4::diskw(&data);
+}
@@ -0,0 +1,82 @@
+// The number type the simulation runs on: `f32`, or `Fixed` with the `fixed-point` feature.
+// Positions, speeds and forces are `Scalar`s and go through these helpers, so the same code
+// builds either way; drawing turns them into `f32` at the last moment.
+//
+// `f32` is already exact to IEEE 754 on every WASM runtime, and `math.rs` is plain arithmetic
+// on it, so replays match between the game and `tools` as they are. Fixed point is for hosts
+// where that does not hold. Replays only play back on a build with the same choice.
+
+#[cfg(feature = "fixed-point")]
+use crate::fixed::Fixed;
+
+#[cfg(not(feature = "fixed-point"))]
+pub type Scalar = f32;
+#[cfg(feature = "fixed-point")]
+pub type Scalar = Fixed;
+
+/// Below this a length counts as zero.
+#[cfg(not(feature = "fixed-point"))]
+pub const EPSILON: Scalar = f32::EPSILON;
+#[cfg(feature = "fixed-point")]
+pub const EPSILON: Scalar = Fixed::from_raw(1);
+
+#[cfg(not(feature = "fixed-point"))]
+pub const INFINITY: Scalar = f32::INFINITY;
+#[cfg(feature = "fixed-point")]
+pub const INFINITY: Scalar = Fixed::MAX;
+
+#[cfg(not(feature = "fixed-point"))]
+pub const fn scalar(value: f32) -> Scalar {
+    value
+}
+
+#[cfg(feature = "fixed-point")]
+pub const fn scalar(value: f32) -> Scalar {
+    Fixed::from_f32(value)
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub const fn to_f32(value: Scalar) -> f32 {
+    value
+}
+
+#[cfg(feature = "fixed-point")]
+pub const fn to_f32(value: Scalar) -> f32 {
+    value.to_f32()
+}
+
+#[cfg(not(feature = "fixed-point"))]
+pub use crate::math::{cos, sin, sqrt};
+
+/// √(x² + y²).
+#[cfg(not(feature = "fixed-point"))]
+pub fn hypot(x: Scalar, y: Scalar) -> Scalar {
+    sqrt(x * x + y * y)
+}
+
+/// `value / (by * by)`.
+#[cfg(not(feature = "fixed-point"))]
+pub fn over_square(value: Scalar, by: Scalar) -> Scalar {
+    value / (by * by)
+}
+
+#[cfg(feature = "fixed-point")]
+pub fn hypot(x: Scalar, y: Scalar) -> Scalar {
+    Fixed::hypot(x, y)
+}
+
+/// Divided twice, since the square is out of range past 181.
+#[cfg(feature = "fixed-point")]
+pub fn over_square(value: Scalar, by: Scalar) -> Scalar {
+    value / by / by
+}
+
+#[cfg(feature = "fixed-point")]
+pub fn sin(value: Scalar) -> Scalar {
+    value.sin()
+}
+
+#[cfg(feature = "fixed-point")]
+pub fn cos(value: Scalar) -> Scalar {
+    value.cos()
+}
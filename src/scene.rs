@@ -0,0 +1,391 @@
+use crate::input::{self, Input, Playback, Recording};
+use crate::menu::Menu;
+use crate::mode::{push_number, Lineup, Mode, Outcome, Teams};
+use crate::player::{Player, MAX_PLAYERS};
+use crate::save;
+use crate::settings::{Setting, Settings};
+use crate::sfx::{Sfx, Sound};
+use crate::wasm4::{self, Framebuffer, Gamepad};
+use crate::world::World;
+use heapless::{String, Vec};
+
+const TITLE: [&str; 3] = ["PLAY", "SETTINGS", "CREDITS"];
+const PAUSE: [&str; 2] = ["RESUME", "QUIT"];
+const CREDITS: [&str; 6] = [
+    "SPACE DELIVERY",
+    "",
+    "A WASM-4 game jam",
+    "entry, made in Rust",
+    "with heapless and",
+    "oorandom.",
+];
+// Frames to fade to black, and again to fade the next scene in.
+const FADE_FRAMES: u8 = 10;
+// Pause by pressing both buttons at once; either on its own is a control.
+const PAUSE_BUTTONS: u8 = wasm4::BUTTON_1 | wasm4::BUTTON_2;
+
+/// What scenes may change besides themselves.
+pub struct Context<'a> {
+    pub world: &'a mut World,
+    pub sfx: &'a mut Sfx,
+    pub settings: &'a mut Settings,
+    pub recording: &'a mut Recording,
+    pub playback: &'a mut Option<Playback<'static>>,
+}
+
+/// One screen of the game; each reads its own input and draws itself.
+pub enum Scene {
+    Title(Menu),
+    Settings(Menu),
+    ModeSelect(Menu),
+    /// Also where other players join, and pick co-op or versus.
+    Briefing(Mode, Lineup),
+    Playing,
+    Paused(Menu),
+    GameOver(Outcome),
+    Credits,
+}
+
+impl Scene {
+    /// Returns the scene to switch to, if any. `pads` holds the buttons that went down
+    /// this frame on each gamepad; only the lobby listens past player one.
+    fn update(&mut self, live: Input, pads: [u8; MAX_PLAYERS], cx: &mut Context) -> Option<Self> {
+        let [pressed, ..] = pads;
+        let back = pressed & wasm4::BUTTON_2 != 0;
+        match *self {
+            Self::Title(ref mut menu) => match menu.update(pressed, TITLE.len(), cx.sfx)? {
+                0 => Some(Self::ModeSelect(Menu::new())),
+                1 => Some(Self::Settings(Menu::new())),
+                _ => Some(Self::Credits),
+            },
+            Self::Settings(ref mut menu) => {
+                let setting = Setting::ALL.get(menu.selected()).copied();
+                let left = pressed & wasm4::BUTTON_LEFT != 0;
+                if let Some(setting) =
+                    setting.filter(|_| left || pressed & wasm4::BUTTON_RIGHT != 0)
+                {
+                    cx.settings.change(setting, !left);
+                    cx.sfx.play(Sound::Ui);
+                    return None;
+                }
+                let picked = if back {
+                    Setting::ALL.len()
+                } else {
+                    menu.update(pressed, Setting::ALL.len() + 1, cx.sfx)?
+                };
+                if let Some(&setting) = Setting::ALL.get(picked) {
+                    cx.settings.change(setting, true);
+                    return None;
+                }
+                save::store(cx.settings, cx.world.upgrades(), cx.world.routes());
+                Some(Self::Title(Menu::at(1)))
+            }
+            Self::ModeSelect(ref mut menu) => {
+                if back {
+                    return Some(Self::Title(Menu::new()));
+                }
+                let picked = menu.update(pressed, Mode::ALL.len(), cx.sfx)?;
+                // Whoever flew last time is still lined up.
+                let lineup = cx.world.lineup();
+                Mode::ALL
+                    .get(picked)
+                    .map(|&mode| Self::Briefing(mode, lineup))
+            }
+            Self::Briefing(mode, ref mut lineup) => {
+                if back {
+                    return Some(Self::ModeSelect(Menu::at(mode_index(mode))));
+                }
+                // X on another gamepad brings its ship in, and any between it and player one.
+                for (index, &others) in pads.iter().enumerate().skip(1) {
+                    let players = u8::try_from(index + 1).unwrap_or(1);
+                    if others & wasm4::BUTTON_1 != 0 && players > lineup.players {
+                        lineup.players = players;
+                        cx.sfx.play(Sound::Ui);
+                    }
+                }
+                let sideways = pressed & (wasm4::BUTTON_LEFT | wasm4::BUTTON_RIGHT) != 0;
+                // A race is always every ship for itself.
+                if lineup.players > 1 && mode != Mode::Race && sideways {
+                    lineup.teams = match lineup.teams {
+                        Teams::CoOp => Teams::Versus,
+                        Teams::Versus => Teams::CoOp,
+                    };
+                    cx.sfx.play(Sound::Ui);
+                }
+                if lineup.players > 1 && pressed & (wasm4::BUTTON_UP | wasm4::BUTTON_DOWN) != 0 {
+                    lineup.bumps = !lineup.bumps;
+                    cx.sfx.play(Sound::Ui);
+                }
+                if pressed & wasm4::BUTTON_1 == 0 {
+                    return None;
+                }
+                *cx.playback = None;
+                cx.world.start(mode, *lineup);
+                cx.recording
+                    .start(cx.world.seed(), mode, *lineup, cx.world.upgrades());
+                Some(Self::Playing)
+            }
+            Self::Playing => {
+                if live.gamepad() & PAUSE_BUTTONS == PAUSE_BUTTONS && pressed & PAUSE_BUTTONS != 0 {
+                    return Some(Self::Paused(Menu::new()));
+                }
+                play(live, cx);
+                cx.world.outcome().map(Self::GameOver)
+            }
+            Self::Paused(ref mut menu) => {
+                if back {
+                    return Some(Self::Playing);
+                }
+                match menu.update(pressed, PAUSE.len(), cx.sfx)? {
+                    0 => Some(Self::Playing),
+                    _ => Some(Self::Title(Menu::new())),
+                }
+            }
+            Self::GameOver(_) => (pressed & wasm4::BUTTON_1 != 0)
+                .then(|| Self::ModeSelect(Menu::at(mode_index(cx.world.mode())))),
+            Self::Credits => (pressed & (wasm4::BUTTON_1 | wasm4::BUTTON_2) != 0)
+                .then(|| Self::Title(Menu::at(2))),
+        }
+    }
+
+    fn draw(&self, world: &World, settings: &Settings, screen: &mut Framebuffer) {
+        match *self {
+            Self::Title(ref menu) => {
+                heading("SPACE DELIVERY", 32);
+                menu.draw(&TITLE, 72);
+            }
+            Self::Settings(ref menu) => {
+                heading("SETTINGS", 16);
+                let mut rows: Vec<String<16>, { Setting::ALL.len() + 1 }> = Setting::ALL
+                    .iter()
+                    .map(|&setting| settings.label(setting))
+                    .collect();
+                rows.push(String::from("BACK")).ok();
+                menu.draw(&rows, 40);
+                hint("\u{84}\u{85} CHANGE  \u{81} BACK");
+            }
+            Self::ModeSelect(ref menu) => {
+                heading("SELECT MODE", 16);
+                menu.draw(&Mode::ALL.map(Mode::name), 48);
+                if let Some(mode) = Mode::ALL.get(menu.selected()) {
+                    lines(&mode.description(), 120);
+                }
+            }
+            Self::Briefing(mode, lineup) => {
+                heading(mode.name(), 32);
+                lines(&mode.description(), 56);
+                let mut players: String<20> = String::new();
+                push_number(&mut players, lineup.players.into());
+                players
+                    .push_str(if lineup.players == 1 {
+                        " PLAYER"
+                    } else {
+                        " PLAYERS"
+                    })
+                    .ok();
+                let mut rows: Vec<&str, 4> = Vec::new();
+                rows.push(&players).ok();
+                if lineup.players > 1 && mode != Mode::Race {
+                    rows.push(match lineup.teams {
+                        Teams::CoOp => "\u{84}\u{85} CO-OP",
+                        Teams::Versus => "\u{84}\u{85} VERSUS",
+                    })
+                    .ok();
+                }
+                if lineup.players > 1 {
+                    rows.push(if lineup.bumps {
+                        "\u{86}\u{87} BUMPS ON"
+                    } else {
+                        "\u{86}\u{87} BUMPS OFF"
+                    })
+                    .ok();
+                }
+                if usize::from(lineup.players) < MAX_PLAYERS {
+                    rows.push("PADS 2-4: \u{80} JOINS").ok();
+                }
+                lines(&rows, 88);
+                hint("\u{80} LAUNCH  \u{81} BACK");
+            }
+            Self::Playing => world.draw(settings, screen),
+            Self::Paused(ref menu) => {
+                world.draw(settings, screen);
+                heading("PAUSED", 48);
+                menu.draw(&PAUSE, 72);
+            }
+            Self::GameOver(outcome) => {
+                world.draw(settings, screen);
+                if world.lineup().players > 1 {
+                    heading("RUN OVER", 24);
+                    lines(&[outcome.describe().as_str()], 40);
+                    results(world, 64);
+                } else {
+                    heading("RUN OVER", 48);
+                    lines(&[outcome.describe().as_str()], 72);
+                }
+                hint("\u{80} CONTINUE");
+            }
+            Self::Credits => {
+                lines(&CREDITS, 32);
+                hint("\u{80} BACK");
+            }
+        }
+    }
+}
+
+/// One frame of a run, on recorded input while there is some.
+fn play(live: Input, cx: &mut Context) {
+    let live = cx.settings.map_input(live);
+    let input = match cx.playback.as_mut().map(Iterator::next) {
+        Some(Some(input)) => input,
+        Some(None) => {
+            wasm4::trace("replay: finished");
+            input::trace_end(cx.world.frame(), cx.world.checksum());
+            *cx.playback = None;
+            cx.world.end_replay();
+            live
+        }
+        None => {
+            cx.recording.push(live);
+            live
+        }
+    };
+    cx.world.update(input, cx.sfx);
+    // A replay beating a time or earning credits is not the player doing it.
+    if cx.world.take_unsaved() && cx.playback.is_none() {
+        save::store(cx.settings, cx.world.upgrades(), cx.world.routes());
+    }
+}
+
+fn mode_index(mode: Mode) -> usize {
+    Mode::ALL
+        .iter()
+        .position(|&other| other == mode)
+        .unwrap_or(0)
+}
+
+/// Centred in the system font's 8 pixel wide characters.
+fn heading(text: &str, y: i32) {
+    wasm4::DrawColors::new().first(4).set();
+    let width = i32::try_from(text.chars().count() * 8).unwrap_or(0);
+    wasm4::text(text, (160 - width).div_euclid(2), y);
+}
+
+fn lines(lines: &[&str], y: i32) {
+    wasm4::DrawColors::new().first(3).set();
+    let mut row = y;
+    for line in lines {
+        wasm4::text(line, 0, row);
+        row += 10;
+    }
+}
+
+/// A table of how each ship did, best first, each row in the ship's colour.
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn results(world: &World, y: i32) {
+    wasm4::DrawColors::new().first(3).set();
+    wasm4::text("   PTS  DIST BMP BST", 0, y);
+    let mut row = y + 12;
+    for (index, stats) in world.standings() {
+        let mut text: String<20> = String::new();
+        text.push('P').ok();
+        push_number(&mut text, u32::try_from(index + 1).unwrap_or(0));
+        push_column(&mut text, stats.score.into(), 4);
+        push_column(&mut text, stats.distance as u32, 6);
+        push_column(&mut text, stats.bumps.into(), 4);
+        push_column(&mut text, stats.boosts.into(), 4);
+        wasm4::DrawColors::new().first(Player::color(index)).set();
+        wasm4::text(text, 0, row);
+        row += 10;
+    }
+}
+
+/// `value` right-aligned in `width` characters.
+fn push_column(text: &mut String<20>, value: u32, width: usize) {
+    let mut number: String<10> = String::new();
+    push_number(&mut number, value);
+    for _ in number.len()..width {
+        text.push(' ').ok();
+    }
+    text.push_str(&number).ok();
+}
+
+fn hint(text: &str) {
+    wasm4::DrawColors::new().first(2).set();
+    wasm4::text(text, 0, 150);
+}
+
+/// Runs the active scene and switches between them through black.
+pub struct Scenes {
+    scene: Scene,
+    // Waiting for the fade out to finish.
+    next: Option<Scene>,
+    // A scene only sees a button when it goes down.
+    gamepads: [Gamepad; MAX_PLAYERS],
+    // 0 is fully visible, `FADE_FRAMES` black.
+    fade: u8,
+}
+
+impl Scenes {
+    pub const fn new(scene: Scene) -> Self {
+        Self {
+            scene,
+            next: None,
+            gamepads: [Gamepad::new(); MAX_PLAYERS],
+            fade: FADE_FRAMES,
+        }
+    }
+
+    pub fn update(&mut self, live: Input, cx: &mut Context) {
+        let mut pads = [0; MAX_PLAYERS];
+        for ((gamepad, pressed), &held) in
+            self.gamepads.iter_mut().zip(&mut pads).zip(&live.gamepads)
+        {
+            gamepad.update(held);
+            *pressed = gamepad.pressed();
+        }
+        if let Some(next) = self.next.take() {
+            // Nothing moves while the screen goes dark.
+            self.fade += 1;
+            if self.fade < FADE_FRAMES {
+                self.next = Some(next);
+            } else {
+                self.scene = next;
+            }
+            return;
+        }
+        self.fade = self.fade.saturating_sub(1);
+        if let Some(next) = self.scene.update(live, pads, cx) {
+            // Pausing and resuming should not blink.
+            if matches!(
+                (&self.scene, &next),
+                (Scene::Playing, Scene::Paused(_)) | (Scene::Paused(_), Scene::Playing)
+            ) {
+                self.scene = next;
+            } else {
+                self.next = Some(next);
+            }
+        }
+    }
+
+    /// For `Palette::update`: 0 is black, 255 full colour.
+    pub fn brightness(&self) -> u8 {
+        let dark = (u16::from(self.fade) * 255).div_euclid(FADE_FRAMES.into());
+        u8::try_from(255 - dark.min(255)).unwrap_or(0)
+    }
+
+    /// Whether the world is on screen, and so whose sector tints the palette.
+    pub const fn shows_world(&self) -> bool {
+        matches!(
+            self.scene,
+            Scene::Playing | Scene::Paused(_) | Scene::GameOver(_)
+        )
+    }
+
+    pub fn draw(&self, world: &World, settings: &Settings, screen: &mut Framebuffer) {
+        self.scene.draw(world, settings, screen);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::input::Input;
+use crate::palette::{self, THEMES};
+use crate::wasm4;
+use heapless::String;
+
+/// Volume steps, in percent.
+const VOLUMES: [u8; 5] = [0, 25, 50, 75, 100];
+
+/// Which button fires the booster; the other one is the stabiliser.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Controls {
+    BoostX,
+    BoostZ,
+}
+
+/// How much help finding the target.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Assist {
+    Off,
+    /// The distance meter along the bottom.
+    Meter,
+    /// The meter and an arrow from the ship towards the target.
+    Arrow,
+}
+
+/// Options the player can change from the settings screen. They are saved with the
+/// best routes, see `save.rs`.
+pub struct Settings {
+    /// Index into `VOLUMES`.
+    pub volume: u8,
+    pub music: bool,
+    /// Index into `palette::THEMES`.
+    pub palette: u8,
+    pub controls: Controls,
+    pub assist: Assist,
+    pub shake: bool,
+}
+
+/// The rows of the settings screen, in order.
+#[derive(Clone, Copy)]
+pub enum Setting {
+    Volume,
+    Music,
+    Palette,
+    Controls,
+    Assist,
+    Shake,
+}
+
+impl Setting {
+    pub const ALL: [Self; 6] = [
+        Self::Volume,
+        Self::Music,
+        Self::Palette,
+        Self::Controls,
+        Self::Assist,
+        Self::Shake,
+    ];
+}
+
+impl Settings {
+    pub const fn new() -> Self {
+        Self {
+            volume: 4,
+            music: true,
+            palette: 0,
+            controls: Controls::BoostX,
+            assist: Assist::Meter,
+            shake: true,
+        }
+    }
+
+    pub fn volume_percent(&self) -> u8 {
+        VOLUMES
+            .get(usize::from(self.volume))
+            .copied()
+            .unwrap_or(100)
+    }
+
+    /// Steps `setting` forwards, or back when `forward` is false, wrapping around.
+    pub fn change(&mut self, setting: Setting, forward: bool) {
+        let step = |value: u8, count: usize| {
+            let count = u8::try_from(count).unwrap_or(1);
+            if forward {
+                (value + 1) % count
+            } else {
+                (value + count - 1) % count
+            }
+        };
+        match setting {
+            Setting::Volume => self.volume = step(self.volume, VOLUMES.len()),
+            Setting::Music => self.music = !self.music,
+            Setting::Palette => {
+                self.palette = step(self.palette, THEMES.len());
+            }
+            Setting::Controls => {
+                self.controls = match self.controls {
+                    Controls::BoostX => Controls::BoostZ,
+                    Controls::BoostZ => Controls::BoostX,
+                };
+            }
+            Setting::Assist => {
+                self.assist = match (self.assist, forward) {
+                    (Assist::Off, true) | (Assist::Arrow, false) => Assist::Meter,
+                    (Assist::Meter, true) | (Assist::Off, false) => Assist::Arrow,
+                    (Assist::Arrow, true) | (Assist::Meter, false) => Assist::Off,
+                };
+            }
+            Setting::Shake => self.shake = !self.shake,
+        }
+    }
+
+    /// What the settings screen shows for `setting`.
+    pub fn label(&self, setting: Setting) -> String<16> {
+        let on_off = |on: bool| if on { "ON" } else { "OFF" };
+        let (name, value) = match setting {
+            Setting::Volume => (
+                "VOLUME",
+                match self.volume_percent() {
+                    0 => "0%",
+                    25 => "25%",
+                    50 => "50%",
+                    75 => "75%",
+                    _ => "100%",
+                },
+            ),
+            Setting::Music => ("MUSIC", on_off(self.music)),
+            Setting::Palette => ("COLORS", palette::theme(self.palette).name),
+            Setting::Controls => (
+                "BOOST",
+                match self.controls {
+                    Controls::BoostX => "X",
+                    Controls::BoostZ => "Z",
+                },
+            ),
+            Setting::Assist => (
+                "ASSIST",
+                match self.assist {
+                    Assist::Off => "OFF",
+                    Assist::Meter => "METER",
+                    Assist::Arrow => "ARROW",
+                },
+            ),
+            Setting::Shake => ("SHAKE", on_off(self.shake)),
+        };
+        let mut label = String::new();
+        label.push_str(name).ok();
+        label.push(' ').ok();
+        label.push_str(value).ok();
+        label
+    }
+
+    /// The game always sees X as boost; swapping happens before input is recorded,
+    /// so replays play the same whatever the settings. It applies to every gamepad, so
+    /// over netplay everyone needs the same setting.
+    pub fn map_input(&self, mut input: Input) -> Input {
+        if self.controls == Controls::BoostZ {
+            let buttons = wasm4::BUTTON_1 | wasm4::BUTTON_2;
+            for gamepad in &mut input.gamepads {
+                let swapped = match *gamepad & buttons {
+                    wasm4::BUTTON_1 => wasm4::BUTTON_2,
+                    wasm4::BUTTON_2 => wasm4::BUTTON_1,
+                    both_or_none => both_or_none,
+                };
+                *gamepad = *gamepad & !buttons | swapped;
+            }
+        }
+        input
+    }
+
+    pub fn to_bytes(&self) -> [u8; 6] {
+        [
+            self.volume,
+            self.music.into(),
+            self.palette,
+            match self.controls {
+                Controls::BoostX => 0,
+                Controls::BoostZ => 1,
+            },
+            match self.assist {
+                Assist::Off => 0,
+                Assist::Meter => 1,
+                Assist::Arrow => 2,
+            },
+            self.shake.into(),
+        ]
+    }
+
+    /// Unknown values fall back to the defaults one by one.
+    pub fn from_bytes(bytes: [u8; 6]) -> Self {
+        let [volume, music, palette, controls, assist, shake] = bytes;
+        let default = Self::new();
+        Self {
+            volume: if usize::from(volume) < VOLUMES.len() {
+                volume
+            } else {
+                default.volume
+            },
+            music: music != 0,
+            palette: if usize::from(palette) < THEMES.len() {
+                palette
+            } else {
+                default.palette
+            },
+            controls: if controls == 1 {
+                Controls::BoostZ
+            } else {
+                Controls::BoostX
+            },
+            assist: match assist {
+                0 => Assist::Off,
+                2 => Assist::Arrow,
+                _ => Assist::Meter,
+            },
+            shake: shake != 0,
+        }
+    }
+}
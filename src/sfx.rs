@@ -0,0 +1,187 @@
+use crate::arrangement::{
+    Arrangement, Channel, Duty, Effect, Frequency, Instrument, Note, NoteLength, Sequence, Wave,
+};
+use crate::theory::{pitch, Tempo};
+use heapless::Vec;
+
+const WAVES: [Wave; 4] = [Wave::Pulse1, Wave::Pulse2, Wave::Triangle, Wave::Noise];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Thrust,
+    Boost,
+    Collision,
+    Pickup,
+    Delivery,
+    Ui,
+}
+
+impl Sound {
+    /// A sound only cuts off one with a lower or equal priority.
+    const fn priority(self) -> u8 {
+        match self {
+            Self::Thrust => 0,
+            Self::Ui => 1,
+            Self::Boost | Self::Pickup => 2,
+            Self::Collision => 3,
+            Self::Delivery => 4,
+        }
+    }
+
+    const fn wave(self) -> Wave {
+        match self {
+            Self::Thrust | Self::Boost | Self::Collision | Self::Ui => Wave::Noise,
+            Self::Pickup | Self::Delivery => Wave::Pulse2,
+        }
+    }
+
+    /// Music volume in percent while this sound plays.
+    const fn music_gain(self) -> u8 {
+        match self {
+            Self::Thrust | Self::Ui | Self::Pickup => 100,
+            Self::Boost => 80,
+            Self::Collision => 70,
+            Self::Delivery => 40,
+        }
+    }
+
+    const fn instrument(self) -> Instrument {
+        let instrument = Instrument {
+            attack: 0,
+            decay: 0,
+            release: 3,
+            volume: 25,
+            peak: 0,
+            duty: Duty::Quarter,
+            effect: Effect::None,
+        };
+        match self {
+            Self::Thrust => Instrument {
+                attack: 1,
+                volume: 6,
+                ..instrument
+            },
+            Self::Boost => Instrument {
+                attack: 2,
+                decay: 6,
+                release: 8,
+                volume: 30,
+                peak: 45,
+                ..instrument
+            },
+            Self::Collision => Instrument {
+                decay: 6,
+                release: 6,
+                volume: 35,
+                peak: 60,
+                ..instrument
+            },
+            Self::Pickup => Instrument {
+                volume: 30,
+                duty: Duty::Eighth,
+                ..instrument
+            },
+            Self::Delivery | Self::Ui => instrument,
+        }
+    }
+
+    // Lengths are in frames, the effects channels tick every frame.
+    fn pattern(self) -> Sequence {
+        let note =
+            |frequency, frames| Note::new(Frequency(Some(frequency)), NoteLength::Ticks(frames));
+        let notes: &[Note] = match self {
+            Self::Thrust => &[note(180, 6)],
+            Self::Boost => &[note(300, 24).with_slide(1800)],
+            Self::Collision => &[note(90, 14).with_slide(60)],
+            Self::Pickup => &[
+                note(pitch("D#5").frequency(), 5),
+                note(pitch("G#5").frequency(), 8),
+            ],
+            // Down the D# minor pentatonic the music is built on.
+            Self::Delivery => &[
+                note(pitch("G#5").frequency(), 7),
+                note(pitch("F#5").frequency(), 7),
+                note(pitch("D#5").frequency(), 7),
+                note(pitch("C#5").frequency(), 7),
+                note(pitch("A#4").frequency(), 7),
+                note(pitch("G#4").frequency(), 7),
+                note(pitch("F#4").frequency(), 7),
+                note(pitch("D#4").frequency(), 7),
+            ],
+            Self::Ui => &[note(1000, 14)],
+        };
+        Sequence::new(Vec::from_slice(notes).unwrap_or_default())
+    }
+}
+
+/// Plays sound effects on their own channels, taking the matching music channel
+/// for as long as they last and ducking the rest of the music.
+pub struct Sfx {
+    channels: Arrangement,
+    playing: [Option<Sound>; 4],
+    // Master volume in percent, for the music too.
+    volume: u8,
+}
+
+impl Sfx {
+    pub const fn new() -> Self {
+        let mut channels = Arrangement::new(
+            Some(Channel::new(Wave::Triangle)),
+            Some(Channel::new(Wave::Pulse1)),
+            Some(Channel::new(Wave::Pulse2)),
+            Some(Channel::new(Wave::Noise)),
+        );
+        channels.set_tempo(Tempo::FRAMES);
+        Self {
+            channels,
+            playing: [None; 4],
+            volume: 100,
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume;
+        self.channels.set_gain(volume);
+    }
+
+    pub fn play(&mut self, sound: Sound) {
+        let wave = sound.wave();
+        let Some(slot) = self.slot(wave) else {
+            return;
+        };
+        if let Some(current) = *slot {
+            if current == sound || current.priority() > sound.priority() {
+                return;
+            }
+        }
+        *slot = Some(sound);
+        self.channels.set_instrument(wave, sound.instrument());
+        self.channels.play_pattern(wave, sound.pattern());
+    }
+
+    /// Call every frame before `music.update()` so stolen channels stay quiet.
+    pub fn update(&mut self, music: &mut Arrangement) {
+        self.channels.update();
+        let mut gain = 100;
+        for (wave, slot) in WAVES.iter().zip(&mut self.playing) {
+            if self.channels.is_free(*wave) {
+                *slot = None;
+            }
+            music.set_muted(*wave, slot.is_some());
+            if let Some(sound) = *slot {
+                gain = gain.min(sound.music_gain());
+            }
+        }
+        music.set_gain(
+            (u16::from(gain) * u16::from(self.volume))
+                .div_euclid(100)
+                .try_into()
+                .unwrap_or(100),
+        );
+    }
+
+    fn slot(&mut self, wave: Wave) -> Option<&mut Option<Sound>> {
+        let index = WAVES.iter().position(|&other| other == wave)?;
+        self.playing.get_mut(index)
+    }
+}
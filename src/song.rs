@@ -0,0 +1,226 @@
+use crate::arrangement::{
+    Arrangement, Duty, Effect, Frequency, Instrument, Note, NoteLength, Sequence, Wave,
+    SEQUENCE_LENGTH,
+};
+use crate::theory::{Pitch, Tempo};
+use heapless::Vec;
+
+// SONG FORMAT, compiled from `music/*.song` by `build.rs`
+// "SDSG" | version | tempo (beats per minute, u16 little endian)
+// 4 x instrument for pulse1, pulse2, triangle, noise:
+//     volume, peak, duty (0..=3, see `Duty`), attack, decay, release,
+//     effect (0 none, 1 vibrato depth speed, 2 arpeggio first second speed), 3 effect arguments
+// pattern count | per pattern: note count, then per note:
+//     midi note (0 = rest), length in ticks (`TICKS_PER_QUARTER` to a quarter),
+//     volume (0xff = instrument), slide to midi note (0 = none)
+// row count | per row: pattern index for pulse1, pulse2, triangle, noise (0xff = silent)
+const MAGIC: &[u8] = b"SDSG";
+pub const VERSION: u8 = 3;
+const MAX_PATTERNS: usize = 64;
+const CHANNELS: [Wave; 4] = [Wave::Pulse1, Wave::Pulse2, Wave::Triangle, Wave::Noise];
+const SILENT: u8 = 0xff;
+const INSTRUMENT_VOLUME: u8 = 0xff;
+const NOTE_SIZE: usize = 4;
+
+pub enum SongError {
+    BadHeader,
+    Truncated,
+    BadInstrument,
+    BadPattern,
+    BadOrder,
+}
+
+impl SongError {
+    pub const fn message(&self) -> &'static str {
+        match *self {
+            Self::BadHeader => "song: not a song or wrong version",
+            Self::Truncated => "song: data ends early",
+            Self::BadInstrument => "song: unknown duty cycle or effect",
+            Self::BadPattern => "song: pattern too long or has an empty note",
+            Self::BadOrder => "song: order refers to a missing pattern",
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, SongError> {
+        let (&first, rest) = self.data.split_first().ok_or(SongError::Truncated)?;
+        self.data = rest;
+        Ok(first)
+    }
+
+    const fn take(&mut self, len: usize) -> Result<&'a [u8], SongError> {
+        if len > self.data.len() {
+            return Err(SongError::Truncated);
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+}
+
+/// A validated song; everything it refers to is known to exist, so playing it cannot fail.
+pub struct Song<'a> {
+    tempo: Tempo,
+    instruments: [Instrument; 4],
+    patterns: Vec<&'a [u8], MAX_PATTERNS>,
+    order: &'a [u8],
+}
+
+impl<'a> Song<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, SongError> {
+        let mut reader = Reader { data };
+        if reader.take(MAGIC.len())? != MAGIC || reader.byte()? != VERSION {
+            return Err(SongError::BadHeader);
+        }
+        let tempo = Tempo::bpm(u16::from_le_bytes([reader.byte()?, reader.byte()?]));
+
+        let mut instruments = [Instrument::DEFAULT; 4];
+        for instrument in &mut instruments {
+            let volume = reader.byte()?;
+            let peak = reader.byte()?;
+            let duty = match reader.byte()? {
+                0 => Duty::Eighth,
+                1 => Duty::Quarter,
+                2 => Duty::Half,
+                3 => Duty::ThreeQuarters,
+                _ => return Err(SongError::BadInstrument),
+            };
+            let attack = reader.byte()?;
+            let decay = reader.byte()?;
+            let release = reader.byte()?;
+            let (kind, first, second, speed) = (
+                reader.byte()?,
+                reader.byte()?,
+                reader.byte()?,
+                reader.byte()?,
+            );
+            let effect = match kind {
+                0 => Effect::None,
+                1 => Effect::Vibrato {
+                    depth: first,
+                    speed,
+                },
+                2 => Effect::Arpeggio {
+                    first,
+                    second,
+                    speed,
+                },
+                _ => return Err(SongError::BadInstrument),
+            };
+            *instrument = Instrument {
+                attack,
+                decay,
+                release,
+                volume,
+                peak,
+                duty,
+                effect,
+            };
+        }
+
+        let mut patterns = Vec::new();
+        for _ in 0..reader.byte()? {
+            let count = usize::from(reader.byte()?);
+            let notes = reader.take(count * NOTE_SIZE)?;
+            if count > SEQUENCE_LENGTH
+                || notes.chunks(NOTE_SIZE).any(|note| note.get(1) == Some(&0))
+            {
+                return Err(SongError::BadPattern);
+            }
+            patterns.push(notes).map_err(|_| SongError::BadPattern)?;
+        }
+
+        let rows = usize::from(reader.byte()?);
+        let order = reader.take(rows * CHANNELS.len())?;
+        if order
+            .iter()
+            .any(|&index| index != SILENT && usize::from(index) >= patterns.len())
+        {
+            return Err(SongError::BadOrder);
+        }
+
+        Ok(Self {
+            tempo,
+            instruments,
+            patterns,
+            order,
+        })
+    }
+
+    const fn rows(&self) -> usize {
+        self.order.len().div_euclid(CHANNELS.len())
+    }
+
+    fn pattern(&self, index: u8) -> Option<Sequence> {
+        let data = self.patterns.get(usize::from(index))?;
+        let mut notes: Vec<Note, SEQUENCE_LENGTH> = Vec::new();
+        for note in data.chunks_exact(NOTE_SIZE) {
+            if let [pitch, ticks, volume, slide] = *note {
+                let frequency = Frequency((pitch != 0).then(|| Pitch(pitch).frequency()));
+                let mut note = Note::new(frequency, NoteLength::Ticks(ticks));
+                if volume != INSTRUMENT_VOLUME {
+                    note = note.with_volume(volume);
+                }
+                if slide != 0 {
+                    note = note.with_slide(Pitch(slide).frequency());
+                }
+                notes.push(note).ok()?;
+            }
+        }
+        Some(Sequence::new(notes))
+    }
+}
+
+/// Feeds a song into an `Arrangement` one row at a time.
+pub struct SongPlayer {
+    song: Song<'static>,
+    row: usize,
+    looping: bool,
+}
+
+impl SongPlayer {
+    pub const fn new(song: Song<'static>, looping: bool) -> Self {
+        Self {
+            song,
+            row: 0,
+            looping,
+        }
+    }
+
+    /// Queues the next row once every channel is done with the last one.
+    /// Returns `false` when a non-looping song has finished.
+    pub fn update(&mut self, music: &mut Arrangement) -> bool {
+        if !music.is_idle() {
+            return true;
+        }
+        if self.row >= self.song.rows() {
+            if !self.looping || self.song.rows() == 0 {
+                for channel in CHANNELS {
+                    music.set_instrument(channel, Instrument::DEFAULT);
+                }
+                return false;
+            }
+            self.row = 0;
+        }
+        if self.row == 0 {
+            music.set_tempo(self.song.tempo);
+            for (channel, instrument) in CHANNELS.iter().zip(self.song.instruments) {
+                music.set_instrument(*channel, instrument);
+            }
+        }
+        let start = self.row * CHANNELS.len();
+        let row = self.song.order.get(start..start + CHANNELS.len());
+        for (channel, &index) in CHANNELS.iter().zip(row.unwrap_or_default()) {
+            if let Some(pattern) = self.song.pattern(index) {
+                music.try_add_pattern(*channel, pattern);
+            }
+        }
+        self.row += 1;
+        true
+    }
+}
@@ -0,0 +1,200 @@
+// Space stations circling some of the planets. Each has a port on the side facing away from its
+// planet; a ship docks by coming in through it slowly and head on, and then rides along with the
+// station until it leaves. Where a station is follows from the frame number alone, so stations
+// need no updating and a replay finds them where they were.
+
+use crate::geometry::{Rect, Vec2};
+use crate::scalar::{scalar, to_f32, Scalar};
+use crate::wasm4;
+use core::f32::consts::TAU;
+
+/// Stations kept at once; building another drops the oldest.
+pub const MAX_STATIONS: usize = 16;
+// One planet in this many gets a station.
+const CHANCE: u32 = 4;
+// Half the width of the hull.
+const SIZE: f32 = 4.0;
+// The port is this far out from the middle, and a ship within `DOCK_RANGE` of it docks.
+const PORT: f32 = 7.0;
+const DOCK_RANGE: f32 = 4.0;
+// Docking speeds, relative to the station: the fastest at all, and the slowest straight in,
+// which keeps a ship that has just left from docking again as the port passes it.
+const DOCK_SPEED: f32 = 0.6;
+const CLOSING_SPEED: f32 = 0.02;
+// Cosine of the widest angle off straight out of the port to come in at, about 30 degrees.
+const DOCK_CONE: f32 = 0.87;
+
+// Line art as segments between points measured out from the middle along the port's axis
+// and across it: a square hull with solar panels either side.
+const HULL: [Segment; 6] = [
+    ((SIZE, SIZE), (SIZE, -SIZE)),
+    ((SIZE, -SIZE), (-SIZE, -SIZE)),
+    ((-SIZE, -SIZE), (-SIZE, SIZE)),
+    ((-SIZE, SIZE), (SIZE, SIZE)),
+    ((0.0, SIZE), (0.0, SIZE + 5.0)),
+    ((0.0, -SIZE), (0.0, -SIZE - 5.0)),
+];
+// Flaring out from the port, the way to come in.
+const PORT_GUIDES: [Segment; 2] = [
+    ((SIZE, 2.0), (SIZE + 3.0, 3.5)),
+    ((SIZE, -2.0), (SIZE + 3.0, -3.5)),
+];
+const ANTENNA: [Segment; 2] = [
+    ((-SIZE, 0.0), (-SIZE - 4.0, 0.0)),
+    ((-SIZE - 4.0, -2.0), (-SIZE - 4.0, 2.0)),
+];
+const TANK: [Segment; 4] = [
+    ((2.0, 2.0), (2.0, -2.0)),
+    ((2.0, -2.0), (-2.0, -2.0)),
+    ((-2.0, -2.0), (-2.0, 2.0)),
+    ((-2.0, 2.0), (2.0, 2.0)),
+];
+const CROSS: [Segment; 2] = [
+    ((SIZE, SIZE), (-SIZE, -SIZE)),
+    ((SIZE, -SIZE), (-SIZE, SIZE)),
+];
+
+type Segment = ((f32, f32), (f32, f32));
+
+/// What a station does for a docked ship.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    /// Deliveries to planets nearby, to take as the next target.
+    Board,
+    /// Fills the tank.
+    Depot,
+    Shop,
+}
+
+impl Service {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Board => "MISSION BOARD",
+            Self::Depot => "FUEL DEPOT",
+            Self::Shop => "SHOP",
+        }
+    }
+
+    /// Drawn on the hull: an antenna for a board, a tank for a depot and a cross for a shop.
+    const fn mark(self) -> &'static [Segment] {
+        match self {
+            Self::Board => &ANTENNA,
+            Self::Depot => &TANK,
+            Self::Shop => &CROSS,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Station {
+    /// The middle of the planet it circles.
+    pub planet: Vec2,
+    orbit: Scalar,
+    // The angle on the first frame, frames for a full turn, and which way round.
+    phase: Scalar,
+    period: u16,
+    clockwise: bool,
+    pub service: Service,
+}
+
+impl Station {
+    /// A station for some planets at `planet` with `radius`, and `None` for the rest. The planet
+    /// and the world seed decide it, rather than the frame's random numbers, so stations change
+    /// nothing else about the world.
+    pub fn around(planet: Vec2, radius: Scalar, seed: u64) -> Option<Self> {
+        let place = u64::from(planet.x.to_bits()) << 32 | u64::from(planet.y.to_bits());
+        let mut random = oorandom::Rand32::new(seed ^ place);
+        if random.rand_range(0..CHANCE) != 0 {
+            return None;
+        }
+        let service = match random.rand_range(0..3) {
+            0 => Service::Board,
+            1 => Service::Depot,
+            _ => Service::Shop,
+        };
+        Some(Self {
+            planet,
+            orbit: radius + random.rand_float() * 20.0 + 15.0,
+            phase: scalar(random.rand_float() * TAU),
+            period: u16::try_from(random.rand_range(1_200..2_400)).unwrap_or(1_200),
+            clockwise: random.rand_u32() & 1 == 0,
+            service,
+        })
+    }
+
+    fn angle(&self, frame: u32) -> Scalar {
+        let into_turn = u16::try_from(frame.rem_euclid(self.period.into())).unwrap_or(0);
+        let turn = f32::from(into_turn) / f32::from(self.period);
+        self.phase + scalar(if self.clockwise { turn } else { -turn } * TAU)
+    }
+
+    /// Straight out from the planet through the station, the way the port faces.
+    pub fn outward(&self, frame: u32) -> Vec2 {
+        Vec2::from_angle(self.angle(frame))
+    }
+
+    pub fn pos(&self, frame: u32) -> Vec2 {
+        self.planet + self.outward(frame) * self.orbit
+    }
+
+    /// How far it moves from `frame` to the next.
+    pub fn vel(&self, frame: u32) -> Vec2 {
+        self.pos(frame.wrapping_add(1)) - self.pos(frame)
+    }
+
+    /// Where a docked ship is held.
+    pub fn port(&self, frame: u32) -> Vec2 {
+        self.pos(frame) + self.outward(frame) * PORT
+    }
+
+    /// Whether a ship at `pos` moving at `vel` comes into the port slowly enough and in line.
+    pub fn docks(&self, frame: u32, pos: Vec2, vel: Vec2) -> bool {
+        let outward = self.outward(frame);
+        let relative = vel - self.vel(frame);
+        let closing = -relative.dot(outward);
+        pos.distance(self.port(frame)) < DOCK_RANGE
+            && relative.magnitude() < DOCK_SPEED
+            && closing > CLOSING_SPEED
+            && (pos - self.pos(frame)).normalized().dot(outward) > DOCK_CONE
+    }
+
+    /// Whether `pos` is on the hull, which a ship bounces off.
+    pub fn hits(&self, frame: u32, pos: Vec2) -> bool {
+        pos.distance(self.pos(frame)) < SIZE * 1.5
+    }
+
+    /// Where a station that might show in `view` could be.
+    pub fn kept_for(view: &Rect) -> Rect {
+        view.grown(PORT + SIZE * 2.0)
+    }
+
+    /// The line art, with the port lit up. Worked out in screen pixels, which are `f32`
+    /// whatever the simulation runs on.
+    #[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+    pub fn draw(&self, view: &Rect, frame: u32) {
+        let center = self.pos(frame) - view.top_left;
+        let out = self.outward(frame);
+        let (x, y, out_x, out_y) = (
+            to_f32(center.x),
+            to_f32(center.y),
+            to_f32(out.x),
+            to_f32(out.y),
+        );
+        let segments = |segments: &[Segment]| {
+            for &((along, across), (to_along, to_across)) in segments {
+                // Across is a quarter turn clockwise from out.
+                wasm4::line(
+                    (x + out_x * along - out_y * across) as i32,
+                    (y + out_y * along + out_x * across) as i32,
+                    (x + out_x * to_along - out_y * to_across) as i32,
+                    (y + out_y * to_along + out_x * to_across) as i32,
+                );
+            }
+        };
+        wasm4::DrawColors::new().first(3).set();
+        segments(&HULL);
+        segments(self.service.mark());
+        wasm4::DrawColors::new().first(4).set();
+        segments(&PORT_GUIDES);
+    }
+}
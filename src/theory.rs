@@ -0,0 +1,176 @@
+// Mostly `const fn`s so note names and the frequency table are worked out at compile time,
+// where `From` and `get` are not available yet.
+
+/// Divisible by 3 and 4, so triplets and sixteenths both land on whole ticks.
+pub const TICKS_PER_QUARTER: u32 = 12;
+/// Frames in a minute, WASM-4 runs at 60 fps.
+pub const FRAMES_PER_MINUTE: u32 = 60 * 60;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Tempo {
+    bpm: u16,
+}
+
+impl Tempo {
+    /// An eighth note every 7 frames, the speed the game always had.
+    pub const DEFAULT: Self = Self::bpm(257);
+    /// Exactly one tick per frame, for sounds timed in frames.
+    pub const FRAMES: Self = Self::bpm(300);
+
+    pub const fn bpm(bpm: u16) -> Self {
+        Self {
+            bpm: if bpm == 0 { 1 } else { bpm },
+        }
+    }
+
+    /// Ticks gained every frame, in 1/3600ths of a tick.
+    #[allow(clippy::as_conversions)]
+    pub const fn tick_rate(self) -> u32 {
+        self.bpm as u32 * TICKS_PER_QUARTER
+    }
+
+    /// How many frames `ticks` last, rounded to the nearest frame.
+    pub const fn frames(self, ticks: u32) -> u32 {
+        let rate = self.tick_rate();
+        (ticks * FRAMES_PER_MINUTE + rate.div_euclid(2)).div_euclid(rate)
+    }
+}
+
+/// A MIDI note number, 60 is middle C (C4) and 69 is A4 at 440 Hz.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Pitch(pub u8);
+
+impl Pitch {
+    /// Parses names like `C4`, `C#3`, `Eb5` or `A-1`.
+    #[allow(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub const fn parse(name: &str) -> Option<Self> {
+        let (mut semitone, rest): (i32, _) = match *name.as_bytes() {
+            [b'C', ref rest @ ..] => (0, rest),
+            [b'D', ref rest @ ..] => (2, rest),
+            [b'E', ref rest @ ..] => (4, rest),
+            [b'F', ref rest @ ..] => (5, rest),
+            [b'G', ref rest @ ..] => (7, rest),
+            [b'A', ref rest @ ..] => (9, rest),
+            [b'B', ref rest @ ..] => (11, rest),
+            _ => return None,
+        };
+        let rest = match *rest {
+            [b'#', ref rest @ ..] => {
+                semitone += 1;
+                rest
+            }
+            [b'b', ref rest @ ..] => {
+                semitone -= 1;
+                rest
+            }
+            _ => rest,
+        };
+        let (negative, mut rest) = match *rest {
+            [b'-', ref rest @ ..] => (true, rest),
+            _ => (false, rest),
+        };
+        if rest.is_empty() {
+            return None;
+        }
+        let mut octave: i32 = 0;
+        while let [digit, ref tail @ ..] = *rest {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            octave = octave * 10 + (digit - b'0') as i32;
+            rest = tail;
+        }
+        if negative {
+            octave = -octave;
+        }
+        let midi = (octave + 1) * 12 + semitone;
+        if midi < 0 || midi > 127 {
+            return None;
+        }
+        Some(Self(midi as u8))
+    }
+
+    #[allow(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub const fn transpose(self, semitones: i8) -> Self {
+        let midi = self.0 as i16 + semitones as i16;
+        Self(if midi < 0 {
+            0
+        } else if midi > 127 {
+            127
+        } else {
+            midi as u8
+        })
+    }
+
+    /// Equal-temperament frequency in Hz, rounded.
+    pub fn frequency(self) -> u16 {
+        FREQUENCIES.get(usize::from(self.0)).copied().unwrap_or(0)
+    }
+}
+
+/// `Pitch::parse` for constants; a bad name is a compile error.
+pub const fn pitch(name: &str) -> Pitch {
+    match Pitch::parse(name) {
+        Some(pitch) => pitch,
+        None => panic!("not a note name"),
+    }
+}
+
+const SEMITONE: f64 = 1.059_463_094_359_295_3;
+const FREQUENCIES: [u16; 128] = equal_temperament();
+
+// Walks out from A4 = 440 Hz a twelfth root of two at a time. Every index is checked by its
+// loop, and a slip would fail the build rather than panic at run time.
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::indexing_slicing
+)]
+const fn equal_temperament() -> [u16; 128] {
+    let mut table = [0; 128];
+    let mut frequency = 440.0;
+    let mut note = 69;
+    while note < 128 {
+        table[note] = (frequency + 0.5) as u16;
+        frequency *= SEMITONE;
+        note += 1;
+    }
+    let mut frequency = 440.0;
+    let mut note = 69;
+    while note > 0 {
+        note -= 1;
+        frequency /= SEMITONE;
+        table[note] = (frequency + 0.5) as u16;
+    }
+    table
+}
+
+/// Moves any frequency by `semitones`, for effects that work on whatever is playing.
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn transpose_frequency(frequency: u16, semitones: i8) -> u16 {
+    let mut ratio = 1.0;
+    let mut steps = semitones;
+    while steps > 0 {
+        ratio *= SEMITONE;
+        steps -= 1;
+    }
+    while steps < 0 {
+        ratio /= SEMITONE;
+        steps += 1;
+    }
+    let transposed = f64::from(frequency) * ratio + 0.5;
+    transposed.clamp(0.0, f64::from(u16::MAX)) as u16
+}
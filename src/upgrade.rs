@@ -0,0 +1,148 @@
+// Ship upgrades, bought at station shops with the credits deliveries earn. Each goes up to
+// `MAX_LEVEL`; level 0 is the ship as it always was, so runs without upgrades play the same.
+// They are saved with the settings, see `save.rs`, and recorded with a replay, see `input.rs`.
+
+use crate::scalar::{scalar, Scalar};
+
+pub const MAX_LEVEL: u8 = 3;
+/// Credits for each package delivered.
+pub const PAY: u16 = 10;
+// Stations further than this per scanner level from the middle of the screen are not marked.
+const SCANNER_RANGE: f32 = 250.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+    /// Thrust.
+    Engine,
+    TopSpeed,
+    /// Fuel in a survival run.
+    Tank,
+    Stabiliser,
+    /// Packages carried, and so paid for, on each delivery.
+    Hold,
+    /// How far off screen stations are marked on its edge.
+    Scanner,
+}
+
+impl Upgrade {
+    pub const ALL: [Self; 6] = [
+        Self::Engine,
+        Self::TopSpeed,
+        Self::Tank,
+        Self::Stabiliser,
+        Self::Hold,
+        Self::Scanner,
+    ];
+
+    /// At most 10 characters, to leave room for the price in the shop.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Engine => "ENGINE",
+            Self::TopSpeed => "TOP SPEED",
+            Self::Tank => "FUEL TANK",
+            Self::Stabiliser => "STABILISER",
+            Self::Hold => "CARGO HOLD",
+            Self::Scanner => "SCANNER",
+        }
+    }
+
+    // The first level's price; each one after costs that much more again.
+    const fn price(self) -> u16 {
+        match self {
+            Self::Engine | Self::TopSpeed => 30,
+            Self::Tank | Self::Stabiliser | Self::Scanner => 20,
+            Self::Hold => 40,
+        }
+    }
+
+    const fn index(self) -> usize {
+        match self {
+            Self::Engine => 0,
+            Self::TopSpeed => 1,
+            Self::Tank => 2,
+            Self::Stabiliser => 3,
+            Self::Hold => 4,
+            Self::Scanner => 5,
+        }
+    }
+}
+
+/// The levels bought so far, and the credits left to buy more with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Upgrades {
+    pub credits: u16,
+    levels: [u8; Upgrade::ALL.len()],
+}
+
+impl Upgrades {
+    pub const NONE: Self = Self {
+        credits: 0,
+        levels: [0; Upgrade::ALL.len()],
+    };
+
+    pub fn level(&self, upgrade: Upgrade) -> u8 {
+        self.levels.get(upgrade.index()).copied().unwrap_or(0)
+    }
+
+    /// What the next level costs, or `None` once it is at the top.
+    pub fn price(&self, upgrade: Upgrade) -> Option<u16> {
+        let level = self.level(upgrade);
+        (level < MAX_LEVEL).then(|| upgrade.price() * u16::from(level + 1))
+    }
+
+    /// Buys the next level of `upgrade`, if there is one and the credits cover it.
+    pub fn buy(&mut self, upgrade: Upgrade) -> bool {
+        let Some(price) = self.price(upgrade).filter(|&price| price <= self.credits) else {
+            return false;
+        };
+        let Some(level) = self.levels.get_mut(upgrade.index()) else {
+            return false;
+        };
+        *level += 1;
+        self.credits -= price;
+        true
+    }
+
+    /// Pays for a delivery, less the percentage of it that arrived `damaged`.
+    pub fn deliver(&mut self, damaged: u8) {
+        let packages = 1 + u16::from(self.level(Upgrade::Hold));
+        let intact = 100 - u16::from(damaged.min(100));
+        self.credits = self
+            .credits
+            .saturating_add((PAY * packages * intact).div_euclid(100));
+    }
+
+    /// How many times better than the ship it started as: a quarter more each level. Exactly
+    /// 1 at level 0, so multiplying by it changes nothing.
+    pub fn factor(&self, upgrade: Upgrade) -> Scalar {
+        scalar(1.0 + f32::from(self.level(upgrade)) / 4.0)
+    }
+
+    /// A tank of `fuel` made bigger by a quarter each level.
+    pub fn tank(&self, fuel: u16) -> u16 {
+        fuel.div_euclid(4)
+            .saturating_mul(4 + u16::from(self.level(Upgrade::Tank)))
+    }
+
+    /// How far off screen the scanner reaches, nothing without one.
+    pub fn scanner_range(&self) -> Scalar {
+        scalar(f32::from(self.level(Upgrade::Scanner)) * SCANNER_RANGE)
+    }
+
+    /// Credits in little endian, then each level in the order of `Upgrade::ALL`.
+    pub const fn to_bytes(self) -> [u8; 8] {
+        let [low, high] = self.credits.to_le_bytes();
+        let [engine, speed, tank, stabiliser, hold, scanner] = self.levels;
+        [low, high, engine, speed, tank, stabiliser, hold, scanner]
+    }
+
+    /// Levels past the top are taken as the top.
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        let [low, high, engine, speed, tank, stabiliser, hold, scanner] = bytes;
+        Self {
+            credits: u16::from_le_bytes([low, high]),
+            levels: [engine, speed, tank, stabiliser, hold, scanner]
+                .map(|level| level.min(MAX_LEVEL)),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 //
 // WASM-4: https://wasm4.org/docs
 
-#![allow(unused)]
+use core::ptr::{with_exposed_provenance, with_exposed_provenance_mut};
+use core::sync::atomic::{AtomicBool, Ordering};
 
 // ┌───────────────────────────────────────────────────────────────────────────┐
 // │                                                                           │
@@ -17,17 +18,17 @@ pub const SCREEN_SIZE: u32 = 160;
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
-pub static mut PALETTE: *mut [u32; 4] = 0x04 as *mut [u32; 4];
-pub const DRAW_COLORS: *mut u16 = 0x14 as *mut u16;
-pub const GAMEPAD1: *const u8 = 0x16 as *const u8;
-pub const GAMEPAD2: *const u8 = 0x17 as *const u8;
-pub const GAMEPAD3: *const u8 = 0x18 as *const u8;
-pub const GAMEPAD4: *const u8 = 0x19 as *const u8;
-pub const MOUSE_X: *const i16 = 0x1a as *const i16;
-pub const MOUSE_Y: *const i16 = 0x1c as *const i16;
-pub const MOUSE_BUTTONS: *const u8 = 0x1e as *const u8;
-pub const SYSTEM_FLAGS: *mut u8 = 0x1f as *mut u8;
-pub static mut FRAMEBUFFER: *mut [u8; 6400] = 0xa0 as *mut [u8; 6400];
+// The addresses are WASM-4's to hand out, so the pointers take whatever provenance it exposed.
+const PALETTE: *mut [u32; 4] = with_exposed_provenance_mut(0x04);
+const DRAW_COLORS: *mut u16 = with_exposed_provenance_mut(0x14);
+const GAMEPAD1: *const u8 = with_exposed_provenance(0x16);
+const GAMEPAD2: *const u8 = with_exposed_provenance(0x17);
+const GAMEPAD3: *const u8 = with_exposed_provenance(0x18);
+const GAMEPAD4: *const u8 = with_exposed_provenance(0x19);
+const MOUSE_X: *const i16 = with_exposed_provenance(0x1a);
+const MOUSE_Y: *const i16 = with_exposed_provenance(0x1c);
+const MOUSE_BUTTONS: *const u8 = with_exposed_provenance(0x1e);
+const FRAMEBUFFER: *mut [u8; 6400] = with_exposed_provenance_mut(0xa0);
 
 pub const BUTTON_1: u8 = 1;
 pub const BUTTON_2: u8 = 2;
@@ -38,10 +39,170 @@ pub const BUTTON_DOWN: u8 = 128;
 
 pub const MOUSE_LEFT: u8 = 1;
 pub const MOUSE_RIGHT: u8 = 2;
-pub const MOUSE_MIDDLE: u8 = 4;
 
-pub const SYSTEM_PRESERVE_FRAMEBUFFER: u8 = 1;
-pub const SYSTEM_HIDE_GAMEPAD_OVERLAY: u8 = 2;
+// ┌───────────────────────────────────────────────────────────────────────────┐
+// │                                                                           │
+// │ Safe Access                                                               │
+// │                                                                           │
+// └───────────────────────────────────────────────────────────────────────────┘
+// The game goes through these rather than the addresses above. WASM-4 runs the cart on
+// one thread and never hands out references to its memory, so plain reads and writes
+// of the registers cannot race.
+
+/// Which palette colours drawing uses, a slot at a time: 0 leaves pixels as they are,
+/// 1 to 4 pick from the palette.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawColors(u16);
+
+impl DrawColors {
+    /// Every slot transparent.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Lines, text, shape fills and the set bits of 1BPP sprites.
+    pub const fn first(self, color: u8) -> Self {
+        self.slot(0, color)
+    }
+
+    /// Shape outlines, text backgrounds and the clear bits of 1BPP sprites.
+    pub const fn second(self, color: u8) -> Self {
+        self.slot(4, color)
+    }
+
+    /// Only used by 2BPP sprites.
+    pub const fn third(self, color: u8) -> Self {
+        self.slot(8, color)
+    }
+
+    /// Only used by 2BPP sprites.
+    pub const fn fourth(self, color: u8) -> Self {
+        self.slot(12, color)
+    }
+
+    #[allow(clippy::as_conversions)]
+    const fn slot(self, shift: u16, color: u8) -> Self {
+        let color = (color & 0xf) as u16;
+        Self(self.0 & !(0xf << shift) | color << shift)
+    }
+
+    /// The colour in the first slot.
+    #[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+    pub const fn first_color(self) -> u8 {
+        (self.0 & 0xf) as u8
+    }
+
+    /// Used by every drawing call after this one.
+    pub fn set(self) {
+        unsafe {
+            *DRAW_COLORS = self.0;
+        }
+    }
+}
+
+/// Replaces the four colours of the palette, darkest first.
+pub fn set_palette(colors: [u32; 4]) {
+    unsafe {
+        *PALETTE = colors;
+    }
+}
+
+/// One player's buttons this frame and the frame before, to tell presses from holds.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Gamepad {
+    held: u8,
+    last: u8,
+}
+
+impl Gamepad {
+    /// Starts as if every button were held, so one held down from before has to be let go
+    /// before it counts as pressed.
+    pub const fn new() -> Self {
+        Self {
+            held: u8::MAX,
+            last: u8::MAX,
+        }
+    }
+
+    /// The buttons held on gamepad `player`, 0 to 3; anything past that reads nothing.
+    pub const fn read(player: usize) -> u8 {
+        let gamepad = match player {
+            0 => GAMEPAD1,
+            1 => GAMEPAD2,
+            2 => GAMEPAD3,
+            3 => GAMEPAD4,
+            _ => return 0,
+        };
+        unsafe { *gamepad }
+    }
+
+    /// Moves on a frame. Takes the buttons rather than reading them, so recorded input
+    /// goes through here the same as live input.
+    pub const fn update(&mut self, buttons: u8) {
+        self.last = self.held;
+        self.held = buttons;
+    }
+
+    /// Buttons that went down this frame.
+    pub const fn pressed(self) -> u8 {
+        self.held & !self.last
+    }
+}
+
+/// Where the mouse is, in screen pixels, and which of the `MOUSE_*` buttons are down.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Mouse {
+    pub x: i16,
+    pub y: i16,
+    pub buttons: u8,
+}
+
+impl Mouse {
+    pub const fn read() -> Self {
+        unsafe {
+            Self {
+                x: *MOUSE_X,
+                y: *MOUSE_Y,
+                buttons: *MOUSE_BUTTONS,
+            }
+        }
+    }
+}
+
+/// The screen, 2 bits a pixel and four pixels to a byte, leftmost in the low bits.
+/// Pixels hold palette indices 0 to 3; outside the screen reads 0 and writes nothing.
+/// There is only ever one, so only whoever holds it draws.
+pub struct Framebuffer(*mut [u8; 6400]);
+
+static FRAMEBUFFER_TAKEN: AtomicBool = AtomicBool::new(false);
+
+impl Framebuffer {
+    /// The screen, the first time; `None` after that.
+    pub fn take() -> Option<Self> {
+        (!FRAMEBUFFER_TAKEN.swap(true, Ordering::Relaxed)).then_some(Self(FRAMEBUFFER))
+    }
+
+    // Only a handle borrowed mutably may draw.
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: u8) {
+        let Some((index, shift)) = Self::locate(x, y) else {
+            return;
+        };
+        let framebuffer = unsafe { &mut *self.0 };
+        if let Some(byte) = framebuffer.get_mut(index) {
+            *byte = (color & 0b11) << shift | *byte & !(0b11 << shift);
+        }
+    }
+
+    // The byte holding (x, y) and where in it the pixel is.
+    fn locate(x: u32, y: u32) -> Option<(usize, u32)> {
+        if x >= SCREEN_SIZE || y >= SCREEN_SIZE {
+            return None;
+        }
+        let index = usize::try_from((y * SCREEN_SIZE + x) >> 2).ok()?;
+        Some((index, (x & 0b11) << 1))
+    }
+}
 
 // ┌───────────────────────────────────────────────────────────────────────────┐
 // │                                                                           │
@@ -58,53 +219,7 @@ extern "C" {
     fn extern_blit(sprite: *const u8, x: i32, y: i32, width: u32, height: u32, flags: u32);
 }
 
-/// Copies a subregion within a larger sprite atlas to the framebuffer.
-#[allow(clippy::too_many_arguments)]
-pub fn blit_sub(
-    sprite: &[u8],
-    x: i32,
-    y: i32,
-    width: u32,
-    height: u32,
-    src_x: u32,
-    src_y: u32,
-    stride: u32,
-    flags: u32,
-) {
-    unsafe {
-        extern_blit_sub(
-            sprite.as_ptr(),
-            x,
-            y,
-            width,
-            height,
-            src_x,
-            src_y,
-            stride,
-            flags,
-        )
-    }
-}
-extern "C" {
-    #[link_name = "blitSub"]
-    fn extern_blit_sub(
-        sprite: *const u8,
-        x: i32,
-        y: i32,
-        width: u32,
-        height: u32,
-        src_x: u32,
-        src_y: u32,
-        stride: u32,
-        flags: u32,
-    );
-}
-
-pub const BLIT_2BPP: u32 = 1;
 pub const BLIT_1BPP: u32 = 0;
-pub const BLIT_FLIP_X: u32 = 2;
-pub const BLIT_FLIP_Y: u32 = 4;
-pub const BLIT_ROTATE: u32 = 8;
 
 /// Draws a line between two points.
 pub fn line(x1: i32, y1: i32, x2: i32, y2: i32) {
@@ -143,30 +258,6 @@ extern "C" {
     fn extern_text(text: *const u8, length: usize, x: i32, y: i32);
 }
 
-/// Draws a vertical line
-pub fn vline(x: i32, y: i32, len: u32) {
-    unsafe {
-        extern_vline(x, y, len);
-    }
-}
-
-extern "C" {
-    #[link_name = "vline"]
-    fn extern_vline(x: i32, y: i32, len: u32);
-}
-
-/// Draws a horizontal line
-pub fn hline(x: i32, y: i32, len: u32) {
-    unsafe {
-        extern_hline(x, y, len);
-    }
-}
-
-extern "C" {
-    #[link_name = "hline"]
-    fn extern_hline(x: i32, y: i32, len: u32);
-}
-
 // ┌───────────────────────────────────────────────────────────────────────────┐
 // │                                                                           │
 // │ Sound Functions                                                           │
@@ -197,12 +288,22 @@ pub const TONE_MODE4: u32 = 12;
 // │                                                                           │
 // └───────────────────────────────────────────────────────────────────────────┘
 
+/// Reads up to `dest.len()` bytes from persistent storage, returning how many were read.
+pub fn diskr(dest: &mut [u8]) -> u32 {
+    unsafe { extern_diskr(dest.as_mut_ptr(), dest.len().try_into().unwrap_or(0)) }
+}
 extern "C" {
-    /// Reads up to `size` bytes from persistent storage into the pointer `dest`.
-    pub fn diskr(dest: *mut u8, size: u32) -> u32;
+    #[link_name = "diskr"]
+    fn extern_diskr(dest: *mut u8, size: u32) -> u32;
+}
 
-    /// Writes up to `size` bytes from the pointer `src` into persistent storage.
-    pub fn diskw(src: *const u8, size: u32) -> u32;
+/// Writes all of `src` to persistent storage, returning how many bytes were written.
+pub fn diskw(src: &[u8]) -> u32 {
+    unsafe { extern_diskw(src.as_ptr(), src.len().try_into().unwrap_or(0)) }
+}
+extern "C" {
+    #[link_name = "diskw"]
+    fn extern_diskw(src: *const u8, size: u32) -> u32;
 }
 
 // ┌───────────────────────────────────────────────────────────────────────────┐
@@ -1,129 +1,516 @@
-use crate::arrangement::{Arrangement, Sequence, Wave};
-use crate::common::{map, Rect, Vec2};
-use crate::gfx::draw_pixel;
+use crate::common::map;
+use crate::composer::Mood;
+use crate::geometry::{Circle, Ray, Rect, Segment, Vec2};
+use crate::gfx::{draw_disc, line};
+use crate::ghost::{Ghost, RouteRecorder, Routes};
+use crate::grid::{self, Grid};
+use crate::input::Input;
+use crate::math::floor;
+use crate::menu::Menu;
+use crate::mode::{
+    push_number, Lineup, Mode, Outcome, BOOST_FUEL, FUEL, RACE_LEGS, TIME_TRIAL_DELIVERIES,
+};
+use crate::palette::SECTOR_SIZE;
 use crate::particle::{PhysicsObject, MAX_LIFETIME};
-use crate::player::Player;
-use crate::wasm4;
+use crate::planet::Planet;
+use crate::player::{Player, MAX_PLAYERS};
+use crate::ring::Ring;
+use crate::scalar::{scalar, to_f32, Scalar, INFINITY};
+use crate::settings::{Assist, Settings};
+use crate::sfx::{Sfx, Sound};
+use crate::station::{Service, Station, MAX_STATIONS};
+use crate::upgrade::{Upgrade, Upgrades, MAX_LEVEL};
+use crate::wasm4::{self, Framebuffer, Gamepad};
 use core::f32::consts::PI;
-use heapless::{HistoryBuffer, Vec};
+use heapless::{String, Vec};
+
+// Gap between ships lined up at the start.
+const SPAWN_SPACING: f32 = 16.0;
+// How far a ship may stray from the middle of the others before it is pulled back,
+// which keeps everyone on the one screen, and how hard the pull grows past that.
+const LEASH: f32 = 60.0;
+const LEASH_PULL: f32 = 0.01;
+// Ships closer than this bounce, when the lineup has bumps on.
+const BUMP_DISTANCE: f32 = 6.0;
+// In a race, a ship this much further from the target than the closest one gets the most
+// extra thrust, `CATCH_UP` times the normal amount.
+const CATCH_UP_RANGE: f32 = 300.0;
+const CATCH_UP: f32 = 0.5;
+// Planets pull on anything this close, and count as nearby up to `NEARBY` from the view.
+const GRAVITY_RANGE: f32 = 114.0 * 3.0;
+const NEARBY: f32 = 114.0 * 5.0;
+// Planets and stars are generated up to this big.
+const MAX_PLANET_RADIUS: f32 = 35.0;
+const MAX_STAR_SIZE: f32 = 2.5;
+// A new target is picked from planets this close to the ships, and a mission board lists
+// deliveries to up to `MAX_JOBS` of the nearest ones this close to its station.
+const TARGET_RANGE: f32 = 700.0;
+const MAX_JOBS: usize = 3;
+// A new target is the planet nearest a random spot in range, up to this far from the spot.
+const TARGET_SEARCH: f32 = 150.0;
+// Spots looked around for a new target before giving up until the next second.
+const TARGET_TRIES: u8 = 10;
+// How fast a ship leaving a station is pushed out of the port.
+const UNDOCK_SPEED: f32 = 0.5;
+// Once the ships are this many sectors out, everything is moved back that far, which keeps
+// positions well inside what fixed point holds.
+const RECENTRE: i32 = 8;
+const RECENTRE_DISTANCE: f32 = 8.0 * SECTOR_SIZE;
+
+/// How one ship's run went, for the leaderboard and the results.
+#[derive(Clone, Copy)]
+pub struct Stats {
+    /// Deliveries, or races legs won.
+    pub score: u16,
+    /// Pixels flown.
+    pub distance: f32,
+    /// Times it ran into another ship.
+    pub bumps: u16,
+    pub boosts: u16,
+}
+
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            score: 0,
+            distance: 0.0,
+            bumps: 0,
+            boosts: 0,
+        }
+    }
+}
+
+struct Ship {
+    player: Player,
+    stats: Stats,
+    // This frame's buttons and the last, for the station menus.
+    pad: Gamepad,
+    dock: Option<Dock>,
+    // Percent of its package spoiled by scorching in the air. All cargo is fragile, so this
+    // counts whenever there is a target, and the ship that delivers is paid for what is left.
+    damage: u8,
+}
+
+/// A ship held at a station's port. It keeps its own copy of the station, which stays put
+/// even if the world drops the station meanwhile.
+struct Dock {
+    station: Station,
+    menu: Menu,
+}
+
 pub struct World {
     view: Rect,
-    player: Player,
-    planets: HistoryBuffer<Planet, 255>,
-    stars: HistoryBuffer<Star, 255>,
+    // Sectors everything has been moved back by, see `recentre`.
+    origin: (i32, i32),
+    ships: Vec<Ship, MAX_PLAYERS>,
+    lineup: Lineup,
+    planets: Ring<Planet, 255>,
+    stars: Ring<Star, 255>,
+    stations: Ring<Station, MAX_STATIONS>,
+    // Where the planets and stars are, kept in step with their rings.
+    planet_cells: Grid,
+    star_cells: Grid,
     particles: Vec<PhysicsObject, 255>,
+    // Frames since the run started.
+    frame: u32,
     seconds_passed: u32,
-    pub mouse_clicked: bool,
+    mouse_clicked: bool,
+    // Deliveries by all ships together.
     score: u16,
     target_planet: Option<Planet>,
+    // Strongest planet pull on any ship during the last update.
+    gravity: Scalar,
+    // Frames left of the delivery fanfare.
+    celebration: u16,
+    // Frames left of shaking the view, after a bump or a delivery.
+    shake: u8,
+    // Mixed with the frame number for everything random, so a replay can rebuild the world.
+    seed: u64,
+    // Best routes so far, the current delivery's route and the best one being raced.
+    // Only solo runs record routes.
+    routes: Routes,
+    route: Option<RouteRecorder>,
+    ghost: Option<Ghost>,
+    // Kept between runs like the routes, and spent at station shops. While a replay plays, the
+    // player's own wait in `own_upgrades`.
+    upgrades: Upgrades,
+    own_upgrades: Option<Upgrades>,
+    // Best routes or upgrades changed since they were last taken for saving.
+    unsaved: bool,
+    mode: Mode,
+    fuel: Option<u16>,
+    outcome: Option<Outcome>,
 }
 
+// `Vec::new` is not inlined, so calling it in `new` builds the particles on the stack first,
+// and there is not room for them there.
+const NO_PARTICLES: Vec<PhysicsObject, 255> = Vec::new();
+
 impl World {
     pub const fn new() -> Self {
-        let stars = HistoryBuffer::new();
+        let stars = Ring::new();
         Self {
-            view: Rect::new(Vec2::new(-80.0, -80.0)),
-            player: Player::new(Vec2::new(0.0, 0.0)),
-            planets: HistoryBuffer::new(),
-            particles: Vec::new(),
+            view: Rect::screen(Vec2::from_f32(-80.0, -80.0)),
+            origin: (0, 0),
+            ships: Vec::new(),
+            lineup: Lineup::SOLO,
+            planets: Ring::new(),
+            particles: NO_PARTICLES,
             stars,
+            stations: Ring::new(),
+            planet_cells: Grid::new(),
+            star_cells: Grid::new(),
+            frame: 0,
             seconds_passed: 0,
             mouse_clicked: false,
             score: 0,
             target_planet: None,
+            gravity: scalar(0.0),
+            celebration: 0,
+            shake: 0,
+            seed: 0,
+            routes: Routes::new(),
+            route: None,
+            ghost: None,
+            upgrades: Upgrades::NONE,
+            own_upgrades: None,
+            unsaved: false,
+            mode: Mode::Explore,
+            fuel: None,
+            outcome: None,
+        }
+    }
+
+    /// Starts a new run from the middle of an empty space, keeping the seed and best routes.
+    pub fn start(&mut self, mode: Mode, lineup: Lineup) {
+        self.view = Rect::screen(Vec2::from_f32(-80.0, -80.0));
+        self.origin = (0, 0);
+        self.ships.clear();
+        for index in 0..lineup.players {
+            // Side by side around the middle; a lone ship starts right on it.
+            let x = (f32::from(index) * 2.0 + 1.0 - f32::from(lineup.players)) * SPAWN_SPACING;
+            let mut player = Player::new(Vec2::from_f32(x, 0.0));
+            player.fit(&self.upgrades);
+            let ship = Ship {
+                player,
+                stats: Stats::new(),
+                pad: Gamepad::new(),
+                dock: None,
+                damage: 0,
+            };
+            if self.ships.push(ship).is_err() {
+                break;
+            }
+        }
+        self.lineup = lineup;
+        // Cleared in place; a fresh `World` does not fit on the stack.
+        self.planets.clear();
+        self.stars.clear();
+        self.stations.clear();
+        self.planet_cells.clear();
+        self.star_cells.clear();
+        self.clear_particles();
+        self.frame = 0;
+        self.seconds_passed = 0;
+        self.mouse_clicked = false;
+        self.score = 0;
+        self.target_planet = None;
+        self.gravity = scalar(0.0);
+        self.celebration = 0;
+        self.shake = 0;
+        self.route = None;
+        self.ghost = None;
+        self.mode = mode;
+        self.fuel = mode.fuel().map(|fuel| self.upgrades.tank(fuel));
+        self.outcome = None;
+    }
+
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub const fn lineup(&self) -> Lineup {
+        self.lineup
+    }
+
+    /// Midway between the ships, where the camera looks.
+    fn center(&self) -> Vec2 {
+        let sum = self
+            .ships
+            .iter()
+            .fold(Vec2::ZERO, |sum, ship| sum + ship.player.physics.pos);
+        sum / f32::from(u8::try_from(self.ships.len()).unwrap_or(1).max(1))
+    }
+
+    /// Which square of space the ships are in, see `palette`.
+    pub fn sector(&self) -> (i32, i32) {
+        let (x, y) = self.moved_sector();
+        (
+            x.saturating_add(self.origin.0),
+            y.saturating_add(self.origin.1),
+        )
+    }
+
+    /// The sector the ships are in since everything was last moved back.
+    #[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+    fn moved_sector(&self) -> (i32, i32) {
+        let pos = self.center();
+        (
+            floor(to_f32(pos.x) / SECTOR_SIZE) as i32,
+            floor(to_f32(pos.y) / SECTOR_SIZE) as i32,
+        )
+    }
+
+    /// Moves everything back by `RECENTRE` sectors once the ships are that far out, so nothing
+    /// ever gets too far from the middle. Only `sector` tells the difference.
+    fn recentre(&mut self) {
+        // Sectors and pixels to move by along one axis.
+        let back = |sector: i32| {
+            if sector >= RECENTRE {
+                (-RECENTRE, scalar(-RECENTRE_DISTANCE))
+            } else if sector <= -RECENTRE {
+                (RECENTRE, scalar(RECENTRE_DISTANCE))
+            } else {
+                (0, scalar(0.0))
+            }
+        };
+        let (x, y) = self.moved_sector();
+        let ((sectors_x, by_x), (sectors_y, by_y)) = (back(x), back(y));
+        if sectors_x == 0 && sectors_y == 0 {
+            return;
+        }
+        let by = Vec2::new(by_x, by_y);
+        self.view = self.view.offset(by);
+        for ship in &mut self.ships {
+            ship.player.shift(by);
+            if let Some(dock) = ship.dock.as_mut() {
+                dock.station.planet += by;
+            }
+        }
+        for planet in self.planets.as_mut_slice() {
+            planet.pos += by;
+        }
+        for star in self.stars.as_mut_slice() {
+            star.pos += by;
+        }
+        for station in self.stations.as_mut_slice() {
+            station.planet += by;
+        }
+        for particle in &mut self.particles {
+            particle.pos += by;
+        }
+        if let Some(target) = self.target_planet.as_mut() {
+            target.pos += by;
+        }
+        self.planet_cells
+            .relink(self.planets.as_slice(), |planet| planet.pos);
+        self.star_cells
+            .relink(self.stars.as_slice(), |star| star.pos);
+        // Best routes are kept by where they lead from the start of a run, so one flown across
+        // the move cannot be saved. The ghost being raced moves along.
+        self.route = None;
+        if let Some(ghost) = self.ghost.as_mut() {
+            ghost.shift(by);
+        }
+        self.origin = (
+            self.origin.0.saturating_sub(sectors_x),
+            self.origin.1.saturating_sub(sectors_y),
+        );
+    }
+
+    /// Frames since the run started.
+    pub const fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Set once a mode's end condition is met; the run should stop being updated then.
+    pub const fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub const fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_routes(&mut self, routes: Routes) {
+        self.routes = routes;
+    }
+
+    pub const fn routes(&self) -> &Routes {
+        &self.routes
+    }
+
+    pub const fn set_upgrades(&mut self, upgrades: Upgrades) {
+        self.upgrades = upgrades;
+    }
+
+    pub const fn upgrades(&self) -> &Upgrades {
+        &self.upgrades
+    }
+
+    /// Flies with the upgrades a replay was recorded with until `end_replay`.
+    pub const fn set_replay_upgrades(&mut self, upgrades: Upgrades) {
+        self.own_upgrades = Some(core::mem::replace(&mut self.upgrades, upgrades));
+    }
+
+    /// Gives the ships back the player's own upgrades, for the rest of the run.
+    pub fn end_replay(&mut self) {
+        if let Some(upgrades) = self.own_upgrades.take() {
+            self.upgrades = upgrades;
+            self.refit();
         }
     }
 
-    fn handle_gamepad(&mut self, gamepad: u8) -> Option<Vec2> {
-        self.player.handle_gamepad(gamepad)
+    fn refit(&mut self) {
+        for ship in &mut self.ships {
+            ship.player.fit(&self.upgrades);
+        }
+    }
+
+    /// Whether a best route was beaten or credits earned or spent since the last call.
+    pub fn take_unsaved(&mut self) -> bool {
+        core::mem::take(&mut self.unsaved)
+    }
+
+    /// A full tank with the upgrades.
+    fn tank(&self) -> u16 {
+        self.upgrades.tank(FUEL)
+    }
+
+    fn handle_mouse(&mut self, input: Input, sfx: &mut Sfx) {
+        if input.mouse_buttons & wasm4::MOUSE_LEFT == 0 {
+            self.mouse_clicked = false;
+        } else {
+            self.mouse_click(input.mouse);
+            sfx.play(Sound::Ui);
+        }
     }
 
-    pub fn mouse_click(&mut self, mouse: (i16, i16)) {
+    fn mouse_click(&mut self, mouse: (i16, i16)) {
         if !self.mouse_clicked {
             let (x, y) = mouse;
             let pos = Vec2::new(
                 self.view.top_left.x + f32::from(x),
                 self.view.top_left.y + f32::from(y),
             );
-            let player = &self.player.physics;
-            self.gen_particle(player.pos, (pos - player.pos).normalized() * 1.50);
+            // The mouse belongs to player one.
+            if let Some(ship) = self.ships.first() {
+                let player = ship.player.physics.pos;
+                self.gen_particle(player, (pos - player).normalized() * 1.50);
+            }
             self.mouse_clicked = true;
         }
     }
 
-    pub fn update(&mut self, time: f64, gamepad: u8, music: &mut Arrangement) {
-        let mut random = oorandom::Rand32::new(unsafe { crate::FRAME_COUNT.into() });
-        let player_pos = self.player.physics.pos;
-        if let Some(particle_force) = self.handle_gamepad(gamepad) {
-            if random.rand_float() < 0.1 {
-                self.gen_particle(player_pos + Vec2::new(0.0, -3.0), particle_force);
+    /// The tank is shared by every ship in the run.
+    fn burn_fuel(&mut self, boosting: bool) {
+        if let Some(fuel) = self.fuel.as_mut() {
+            *fuel = fuel.saturating_sub(if boosting { BOOST_FUEL } else { 1 });
+            if *fuel == 0 {
+                self.outcome = Some(if self.lineup.is_versus(self.mode) {
+                    Outcome::Versus {
+                        winner: self.leader(),
+                    }
+                } else {
+                    Outcome::Survival {
+                        deliveries: self.score,
+                    }
+                });
             }
         }
-        for planet in self.planets.as_slice() {
-            let delta = planet.pos - player_pos;
-            let distance = delta.magnitude();
-            if distance < 114.0 * 3.0 && distance > planet.radius + 2.0 {
-                let force = planet.gravity(distance);
-                self.player.apply_force(delta.normalized() * force * 0.4);
-            } else if distance < planet.radius {
-                self.player.collide(delta);
-            }
+    }
+
+    /// The ship with the most deliveries, unless it shares the lead.
+    fn leader(&self) -> Option<u8> {
+        let best = self.ships.iter().map(|ship| ship.stats.score).max()?;
+        let mut leaders = self
+            .ships
+            .iter()
+            .enumerate()
+            .filter(|&(_, ship)| ship.stats.score == best);
+        match (leaders.next(), leaders.next()) {
+            (Some((index, _)), None) => u8::try_from(index).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn update(&mut self, input: Input, sfx: &mut Sfx) {
+        let frame = self.frame;
+        self.frame += 1;
+        let mut random = oorandom::Rand32::new(self.seed.wrapping_add(frame.into()));
+        self.handle_mouse(input, sfx);
+        let center = self.center();
+        let positions: Vec<Vec2, MAX_PLAYERS> = self
+            .ships
+            .iter()
+            .map(|ship| ship.player.physics.pos)
+            .collect();
+        self.gravity = scalar(0.0);
+        self.celebration = self.celebration.saturating_sub(1);
+        self.shake = self.shake.saturating_sub(1);
+        if self.mode == Mode::Race {
+            self.rubber_band();
+        }
+        for (index, &gamepad) in input.gamepads.iter().enumerate().take(self.ships.len()) {
+            self.update_ship(index, gamepad, &mut random, sfx);
+        }
+        if self.lineup.bumps {
+            self.bump_ships(sfx);
+        }
+        self.tether(center);
+        self.change_view(center);
+        if let (Some(route), Some(ship)) = (self.route.as_mut(), self.ships.first()) {
+            route.update(ship.player.physics.pos);
+        }
+        if let Some(ghost) = self.ghost.as_mut() {
+            ghost.update();
         }
-        self.player.update();
-        self.change_view(player_pos);
 
-        if self.seconds_passed < time as u32 {
+        if self.seconds_passed < frame.div_euclid(60) {
             self.seconds_passed += 1;
-            match &self.target_planet.clone() {
-                Some(target) => {
-                    let distance = player_pos.distance(target.pos);
-                    if distance < target.radius {
-                        self.score += 1;
-                        music.try_add_pattern(
-                            Wave::Pulse2,
-                            Sequence::gen_pattern(11, random.rand_float()),
-                        );
-                        for _ in 0..20 {
-                            self.gen_particle(
-                                target.pos,
-                                Vec2::new(5.0, 0.0).rotated(random.rand_float() * PI * 2.0),
-                            );
-                        }
-                        self.target_planet = None;
-                    } else if distance > 1_000.0 {
-                        self.target_planet = None;
-                    }
-                }
-                None => loop {
-                    let planet = random.rand_range(0..self.planets.len() as u32) as usize;
-                    let possible_planet = self.planets.as_slice()[planet].clone();
-                    if possible_planet.pos.distance(player_pos) < 700.0 {
-                        self.target_planet = Some(possible_planet);
-                        break;
-                    }
-                },
+            if self.mode.has_targets() {
+                self.update_target(&positions, center, &mut random, sfx);
             }
         }
 
         if self.count_planets() < 20 {
-            let planet_size: u8 =
-                unsafe { (random.rand_u32() % 100).try_into().unwrap_unchecked() };
+            let planet_size = u8::try_from(random.rand_u32().rem_euclid(100)).unwrap_or(0);
             let possible_planet = Planet::new(
                 Vec2::new(
                     self.view.center().x + (random.rand_float() - 0.5) * 300.,
                     self.view.center().y + (random.rand_float() - 0.5) * 300.,
                 ),
-                random.rand_float() * 30. + 5.,
+                scalar(random.rand_float() * 30. + 5.),
                 planet_size + 155u8,
+                self.seed,
             );
-            #[allow(clippy::collapsible_if)]
-            if possible_planet.pos.distance(player_pos) > 114.0 + possible_planet.radius {
-                if self.planets.iter().all(|planet| {
-                    planet.pos.distance(possible_planet.pos)
-                        > ((planet.radius + possible_planet.radius) * 3.0)
-                }) {
-                    self.planets.write(possible_planet);
+            // No planet further off than this could be too close.
+            let spacing = Circle::new(
+                possible_planet.pos,
+                (possible_planet.radius + MAX_PLANET_RADIUS) * 3.0,
+            );
+            if positions
+                .iter()
+                .all(|&pos| possible_planet.pos.distance(pos) > 114.0 + possible_planet.radius)
+                && self
+                    .planet_cells
+                    .within(self.planets.as_slice(), &spacing.bounds())
+                    .all(|planet| {
+                        planet.pos.distance(possible_planet.pos)
+                            > ((planet.radius + possible_planet.radius) * 3.0)
+                    })
+            {
+                if let Some(station) =
+                    Station::around(possible_planet.pos, possible_planet.radius, self.seed)
+                {
+                    self.stations.write(station);
                 }
+                self.planet_cells.insert(possible_planet.pos);
+                self.planets.write(possible_planet);
             }
         }
 
@@ -131,171 +518,878 @@ impl World {
             self.gen_star(random);
         }
 
+        self.update_particles();
+        self.recentre();
+    }
+
+    /// Pulls and moves the particles for one frame.
+    fn update_particles(&mut self) {
+        // One look at the grid for every particle, as they are mostly close together. Kept as
+        // indices, which take a quarter of the stack that references would.
+        let reach =
+            |particle: &PhysicsObject| Circle::new(particle.pos, scalar(GRAVITY_RANGE)).bounds();
+        let pulling: Vec<u8, { grid::CAPACITY }> = self
+            .particles
+            .iter()
+            .map(reach)
+            .reduce(|all, one| all.union(&one))
+            .map(|all| {
+                self.planet_cells
+                    .near(&all)
+                    .filter_map(|index| u8::try_from(index).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let planets = self.planets.as_slice();
         for particle in &mut self.particles {
-            for planet in self.planets.as_slice() {
+            let pulled_by = pulling
+                .iter()
+                .filter_map(|&index| planets.get(usize::from(index)));
+            for planet in pulled_by {
                 let delta = planet.pos - particle.pos;
                 let distance = delta.magnitude();
-                if distance < 114.0 * 3.0 && distance > planet.radius + 2.0 {
+                if distance < GRAVITY_RANGE && distance > planet.radius + 2.0 {
                     let force = planet.gravity(distance);
                     particle.apply_force(delta.normalized() * force);
                 }
+                if let Some(drag) = planet.drag(particle.pos) {
+                    particle.drag(drag);
+                }
             }
             particle.update();
         }
     }
 
+    /// Steers, pulls and bumps ship `index` for one frame.
+    fn update_ship(
+        &mut self,
+        index: usize,
+        gamepad: u8,
+        random: &mut oorandom::Rand32,
+        sfx: &mut Sfx,
+    ) {
+        // Only the stabilisers work on an empty tank.
+        let gamepad = if self.fuel == Some(0) {
+            gamepad & wasm4::BUTTON_2
+        } else {
+            gamepad
+        };
+        let Some(ship) = self.ships.get_mut(index) else {
+            return;
+        };
+        ship.pad.update(gamepad);
+        if ship.dock.is_some() {
+            self.update_docked(index, sfx);
+            return;
+        }
+        let player_pos = ship.player.physics.pos;
+        let boosting = gamepad & wasm4::BUTTON_1 != 0
+            && gamepad & wasm4::BUTTON_2 == 0
+            && ship.player.can_boost();
+        if let Some(particle_force) = ship.player.handle_gamepad(gamepad) {
+            if boosting {
+                ship.stats.boosts += 1;
+            }
+            self.burn_fuel(boosting);
+            sfx.play(if boosting {
+                Sound::Boost
+            } else {
+                Sound::Thrust
+            });
+            if random.rand_float() < 0.1 {
+                self.gen_particle(player_pos + Vec2::from_f32(0.0, -3.0), particle_force);
+            }
+        }
+        let tank = self.tank();
+        let Some(ship) = self.ships.get_mut(index) else {
+            return;
+        };
+        let gravity = self.mode.gravity(self.seconds_passed) * 0.4;
+        let reach = Circle::new(player_pos, scalar(GRAVITY_RANGE)).bounds();
+        // Where the ship goes this frame, so a fast one cannot skip through a small planet.
+        let path = Segment::new(player_pos, player_pos + ship.player.physics.vel);
+        for planet in self.planet_cells.within(self.planets.as_slice(), &reach) {
+            let delta = planet.pos - player_pos;
+            let distance = delta.magnitude();
+            if path.intersects_circle(&planet.body()) {
+                ship.player.collide();
+                self.shake = self.shake.max(8);
+                sfx.play(Sound::Collision);
+            } else if distance < GRAVITY_RANGE && distance > planet.radius + 2.0 {
+                let force = planet.gravity(distance) * gravity;
+                self.gravity = self.gravity.max(force);
+                ship.player.apply_force(delta.normalized() * force);
+            }
+            if let Some(drag) = planet.drag(player_pos) {
+                ship.player.heat_up(drag);
+                ship.player.physics.drag(drag);
+            }
+        }
+        if ship.player.is_scorching() && self.target_planet.is_some() {
+            ship.damage = ship.damage.saturating_add(1).min(100);
+        }
+        let player_vel = ship.player.physics.vel;
+        for station in self.stations.as_slice() {
+            if station.docks(self.frame, player_pos, player_vel) {
+                ship.player
+                    .physics
+                    .hold(station.port(self.frame), station.vel(self.frame));
+                ship.dock = Some(Dock {
+                    station: station.clone(),
+                    menu: Menu::new(),
+                });
+                if station.service == Service::Depot {
+                    self.fuel = self.fuel.map(|_| tank);
+                }
+                sfx.play(Sound::Pickup);
+                return;
+            }
+            if station.hits(self.frame, player_pos) {
+                ship.player.collide();
+                self.shake = self.shake.max(8);
+                sfx.play(Sound::Collision);
+            }
+        }
+        ship.player.update();
+        ship.stats.distance += to_f32(ship.player.physics.vel.magnitude());
+    }
+
+    /// Holds docked ship `index` at the port and works the station's menu, if it has one;
+    /// Z leaves.
+    fn update_docked(&mut self, index: usize, sfx: &mut Sfx) {
+        let frame = self.frame;
+        let jobs = match self.ships.get(index).and_then(|ship| ship.dock.as_ref()) {
+            Some(dock) if dock.station.service == Service::Board => self.jobs(&dock.station),
+            Some(_) => Vec::new(),
+            None => return,
+        };
+        let Some(ship) = self.ships.get_mut(index) else {
+            return;
+        };
+        let Some(dock) = ship.dock.as_mut() else {
+            return;
+        };
+        let station = &dock.station;
+        let pressed = ship.pad.pressed();
+        if pressed & wasm4::BUTTON_2 != 0 {
+            let push = station.outward(frame) * UNDOCK_SPEED;
+            ship.player
+                .physics
+                .hold(station.port(frame), station.vel(frame) + push);
+            ship.dock = None;
+            sfx.play(Sound::Ui);
+            return;
+        }
+        ship.player
+            .physics
+            .hold(station.port(frame), station.vel(frame));
+        let service = station.service;
+        let rows = match service {
+            Service::Board => jobs.len(),
+            Service::Depot => 0,
+            Service::Shop => Upgrade::ALL.len(),
+        };
+        let Some(picked) = dock.menu.update(pressed, rows, sfx) else {
+            return;
+        };
+        match service {
+            Service::Board => {
+                if let Some(&planet) = jobs.get(picked) {
+                    self.take_job(planet, sfx);
+                }
+            }
+            Service::Depot => {}
+            Service::Shop => {
+                if let Some(&upgrade) = Upgrade::ALL.get(picked) {
+                    self.buy(upgrade);
+                }
+            }
+        }
+    }
+
+    /// The next level of `upgrade` for every ship, if the credits cover it.
+    fn buy(&mut self, upgrade: Upgrade) {
+        if self.upgrades.buy(upgrade) {
+            self.refit();
+            self.unsaved = true;
+        }
+    }
+
+    /// Planets to deliver to from `station`'s mission board, nearest first, as indices into
+    /// `planets`. None in runs where the target is not up to one player.
+    fn jobs(&self, station: &Station) -> Vec<usize, MAX_JOBS> {
+        let mut jobs: Vec<(usize, Scalar), MAX_JOBS> = Vec::new();
+        if !self.mode.has_targets() || self.mode == Mode::Race || self.lineup.is_versus(self.mode) {
+            return Vec::new();
+        }
+        let planets = self.planets.as_slice();
+        let area = Circle::new(station.planet, scalar(TARGET_RANGE)).bounds();
+        for (index, planet) in self
+            .planet_cells
+            .near(&area)
+            .filter_map(|index| planets.get(index).map(|planet| (index, planet)))
+        {
+            let distance = planet.pos.distance(station.planet);
+            // Not the planet the station goes round.
+            if distance >= TARGET_RANGE || distance < planet.radius || !planet.takes_deliveries() {
+                continue;
+            }
+            // In order as they come in, dropping the furthest once there are enough.
+            let at = jobs
+                .iter()
+                .position(|&(_, other)| distance < other)
+                .unwrap_or(jobs.len());
+            if at < MAX_JOBS {
+                if jobs.is_full() {
+                    jobs.pop();
+                }
+                jobs.push((index, distance)).ok();
+                if let Some(moved) = jobs.get_mut(at..) {
+                    moved.rotate_right(1);
+                }
+            }
+        }
+        jobs.iter().map(|&(index, _)| index).collect()
+    }
+
+    /// Makes planet `index` the target, from the ships where they are now.
+    fn take_job(&mut self, index: usize, sfx: &mut Sfx) {
+        if let Some(planet) = self.planets.as_slice().get(index).cloned() {
+            let center = self.center();
+            self.set_target(planet, center, sfx);
+        }
+    }
+
+    /// Makes `planet` the target, racing the best route to it from `start` in a solo run that
+    /// has not moved back yet.
+    fn set_target(&mut self, planet: Planet, start: Vec2, sfx: &mut Sfx) {
+        if self.ships.len() == 1 && self.origin == (0, 0) {
+            self.route = Some(RouteRecorder::new(planet.pos, start));
+            self.ghost = self.routes.find(planet.pos).map(Ghost::new);
+        }
+        self.target_planet = Some(planet);
+        for ship in &mut self.ships {
+            ship.damage = 0;
+        }
+        sfx.play(Sound::Pickup);
+    }
+
+    /// More thrust for ships further from the target than the closest one.
+    fn rubber_band(&mut self) {
+        let Some(target) = self.target_planet.as_ref().map(|planet| planet.pos) else {
+            for ship in &mut self.ships {
+                ship.player.set_catch_up(scalar(0.0));
+            }
+            return;
+        };
+        let closest = self
+            .ships
+            .iter()
+            .map(|ship| ship.player.physics.pos.distance(target))
+            .fold(INFINITY, Scalar::min);
+        for ship in &mut self.ships {
+            let behind = ship.player.physics.pos.distance(target) - closest;
+            ship.player
+                .set_catch_up((behind / CATCH_UP_RANGE).clamp(scalar(0.0), scalar(1.0)) * CATCH_UP);
+        }
+    }
+
+    /// Ships that touch bounce apart like two equal balls, trading the speed along the
+    /// line between them.
+    fn bump_ships(&mut self, sfx: &mut Sfx) {
+        let mut bumped = false;
+        for second in 1..self.ships.len() {
+            let (before, after) = self.ships.split_at_mut(second);
+            let Some(b) = after.first_mut() else {
+                continue;
+            };
+            for a in before {
+                let delta = b.player.physics.pos - a.player.physics.pos;
+                let distance = delta.magnitude();
+                if distance >= BUMP_DISTANCE || distance <= 0.0 {
+                    continue;
+                }
+                let normal = delta / distance;
+                let relative = a.player.physics.vel - b.player.physics.vel;
+                let closing = relative.dot(normal);
+                // Already moving apart.
+                if closing <= 0.0 {
+                    continue;
+                }
+                a.player.physics.vel -= normal * closing;
+                b.player.physics.vel += normal * closing;
+                a.stats.bumps += 1;
+                b.stats.bumps += 1;
+                bumped = true;
+            }
+        }
+        if bumped {
+            self.shake = self.shake.max(6);
+            sfx.play(Sound::Collision);
+        }
+    }
+
+    /// Ship indices with their stats, best first: most points, then closest to the target,
+    /// then the lineup order.
+    pub fn standings(&self) -> Vec<(usize, Stats), MAX_PLAYERS> {
+        let target = self.target_planet.as_ref().map(|planet| planet.pos);
+        let mut standings: Vec<(usize, Stats, Scalar), MAX_PLAYERS> = self
+            .ships
+            .iter()
+            .enumerate()
+            .map(|(index, ship)| {
+                let distance = target.map_or(scalar(0.0), |target| {
+                    ship.player.physics.pos.distance(target)
+                });
+                (index, ship.stats, distance)
+            })
+            .collect();
+        // Insertion sort: there are four at most, and `sort_by` would add kilobytes to the cart.
+        for sorted in 1..standings.len() {
+            let mut at = sorted;
+            while let (Some(before), Some(this)) = (standings.get(at - 1), standings.get(at)) {
+                let ahead = this.1.score > before.1.score
+                    || (this.1.score == before.1.score && this.2 < before.2);
+                if !ahead {
+                    break;
+                }
+                standings.swap(at - 1, at);
+                at -= 1;
+                if at == 0 {
+                    break;
+                }
+            }
+        }
+        standings
+            .iter()
+            .map(|&(index, stats, _)| (index, stats))
+            .collect()
+    }
+
+    /// Pulls back any ship that strays too far from `center`, so all of them stay on screen.
+    fn tether(&mut self, center: Vec2) {
+        for ship in &mut self.ships {
+            let offset = ship.player.physics.pos - center;
+            let distance = offset.magnitude();
+            if distance > LEASH {
+                ship.player
+                    .apply_force(offset.normalized() * -((distance - LEASH) * LEASH_PULL));
+            }
+        }
+    }
+
+    /// Once a second: checks for a delivery, or picks the next target near `center`.
+    /// `positions` are where the ships were at the start of the frame.
+    fn update_target(
+        &mut self,
+        positions: &[Vec2],
+        center: Vec2,
+        random: &mut oorandom::Rand32,
+        sfx: &mut Sfx,
+    ) {
+        match self.target_planet.clone() {
+            Some(target) => {
+                // With several ships there, the first one in the lineup gets it.
+                let deliverer = positions
+                    .iter()
+                    .position(|&pos| target.body().contains(pos));
+                if let Some(index) = deliverer {
+                    self.score += 1;
+                    let ship_score = self.ships.get_mut(index).map_or(0, |ship| {
+                        ship.stats.score += 1;
+                        ship.stats.score
+                    });
+                    self.celebration = 60 * 4;
+                    self.shake = 15;
+                    let tank = self.tank();
+                    if let Some(fuel) = self.fuel.as_mut() {
+                        *fuel = fuel.saturating_add(tank.div_euclid(2)).min(tank);
+                    }
+                    let damage = self.ships.get(index).map_or(0, |ship| ship.damage);
+                    self.upgrades.deliver(damage);
+                    self.unsaved = true;
+                    if self.mode == Mode::Race && self.score >= RACE_LEGS {
+                        self.outcome = Some(if self.ships.len() == 1 {
+                            Outcome::TimeTrial { frames: self.frame }
+                        } else {
+                            Outcome::Versus {
+                                winner: self.leader(),
+                            }
+                        });
+                    }
+                    if self.mode == Mode::TimeTrial {
+                        if self.lineup.is_versus(self.mode) {
+                            if ship_score >= TIME_TRIAL_DELIVERIES {
+                                self.outcome = Some(Outcome::Versus {
+                                    winner: u8::try_from(index).ok(),
+                                });
+                            }
+                        } else if self.score >= TIME_TRIAL_DELIVERIES {
+                            self.outcome = Some(Outcome::TimeTrial { frames: self.frame });
+                        }
+                    }
+                    sfx.play(Sound::Delivery);
+                    for _ in 0..20 {
+                        self.gen_particle(
+                            target.pos,
+                            Vec2::from_f32(5.0, 0.0)
+                                .rotated(scalar(random.rand_float() * PI * 2.0)),
+                        );
+                    }
+                    self.target_planet = None;
+                    self.ghost = None;
+                    let route = self.route.take();
+                    if let Some(route) = route
+                        .zip(self.ships.first())
+                        .and_then(|(route, ship)| route.finish(ship.player.physics.pos))
+                    {
+                        self.unsaved |= self.routes.insert(route);
+                    }
+                } else if center.distance(target.pos) > 1_000.0 {
+                    self.target_planet = None;
+                    self.route = None;
+                    self.ghost = None;
+                }
+            }
+            // There may be nowhere to deliver to nearby yet; the next second tries again.
+            None => self.pick_target(center, random, sfx),
+        }
+    }
+
+    /// Targets the deliverable planet nearest one of a few random spots in range, if any.
+    fn pick_target(&mut self, center: Vec2, random: &mut oorandom::Rand32, sfx: &mut Sfx) {
+        let deliverable = |planet: &Planet| {
+            (planet.takes_deliveries() && planet.pos.distance(center) < TARGET_RANGE)
+                .then_some(planet.pos)
+        };
+        for _ in 0..TARGET_TRIES {
+            let spot = center
+                + Vec2::from_f32(random.rand_float() * TARGET_RANGE, 0.0)
+                    .rotated(scalar(random.rand_float() * PI * 2.0));
+            if let Some(planet) = self
+                .planet_cells
+                .nearest(
+                    self.planets.as_slice(),
+                    spot,
+                    scalar(TARGET_SEARCH),
+                    deliverable,
+                )
+                .cloned()
+            {
+                self.set_target(planet, center, sfx);
+                break;
+            }
+        }
+    }
+
     fn gen_particle(&mut self, pos: Vec2, force: Vec2) {
         let mut particle = PhysicsObject::new(pos, Some(MAX_LIFETIME));
         particle.apply_force(force);
-        match self.particles.push(particle) {
-            Ok(_) => (),
-            Err(particle) => {
-                self.particles.clear();
-                unsafe {
-                    self.particles.push_unchecked(particle);
-                }
-            }
+        if let Err(particle) = self.particles.push(particle) {
+            self.clear_particles();
+            self.particles.push(particle).ok();
         }
     }
 
+    // `Vec::clear` in heapless 0.7 indexes one past the end as it drops, which debug builds
+    // catch; popping empties it the same way.
+    fn clear_particles(&mut self) {
+        while self.particles.pop().is_some() {}
+    }
+
     fn gen_star(&mut self, mut random: oorandom::Rand32) {
         let star = Star::new(
             Vec2::new(
                 self.view.center_mul(3.0).x + (random.rand_float() - 0.5) * 1000.,
                 self.view.center_mul(3.0).y + (random.rand_float() - 0.5) * 1000.,
             ),
-            unsafe { (random.rand_u32() % 200).try_into().unwrap_unchecked() },
-            random.rand_float() * 2.0 + 0.5,
+            u8::try_from(random.rand_u32().rem_euclid(200)).unwrap_or(0),
+            scalar(random.rand_float() * 2.0 + 0.5),
         );
         let distance = star.pos.distance(self.view.center_mul(3.0));
         if distance > 114.0 * 3.0 && distance < 114.0 * 5.0 {
+            self.star_cells.insert(star.pos);
             self.stars.write(star);
         }
     }
 
+    pub fn mood(&self) -> Mood {
+        if self.celebration > 0 {
+            return Mood::Delivered;
+        }
+        let near = |target: &Planet| {
+            self.ships
+                .iter()
+                .any(|ship| target.pos.distance(ship.player.physics.pos) < 200.0)
+        };
+        match self.target_planet {
+            Some(ref target) if near(target) => Mood::NearTarget,
+            _ if self.gravity > 0.01 => Mood::GravityWell,
+            _ => Mood::Cruising,
+        }
+    }
+
+    /// Cheap fingerprint of the game state, to check that a replay ends where the recording did.
+    pub fn checksum(&self) -> u32 {
+        let target = self
+            .target_planet
+            .as_ref()
+            .map_or(Vec2::ZERO, |planet| planet.pos);
+        let [credits_low, credits_high, engine, speed, tank, stabiliser, hold, scanner] =
+            self.upgrades.to_bytes();
+        let values = [
+            self.origin.0.cast_unsigned(),
+            self.origin.1.cast_unsigned(),
+            target.x.to_bits(),
+            target.y.to_bits(),
+            u32::from(self.score),
+            u32::try_from(self.planets.as_slice().len()).unwrap_or(u32::MAX),
+            self.fuel.map_or(u32::MAX, u32::from),
+            u32::from_le_bytes([credits_low, credits_high, engine, speed]),
+            u32::from_le_bytes([tank, stabiliser, hold, scanner]),
+        ];
+        let ships = self.ships.iter().flat_map(|ship| {
+            let physics = &ship.player.physics;
+            [
+                physics.pos.x.to_bits(),
+                physics.pos.y.to_bits(),
+                physics.vel.x.to_bits(),
+                physics.vel.y.to_bits(),
+                u32::from(ship.stats.score),
+                ship.stats.distance.to_bits(),
+                u32::from(ship.stats.bumps),
+                u32::from(ship.stats.boosts),
+                u32::from(ship.damage),
+                ship.player.heat().to_bits(),
+            ]
+        });
+        // FNV-1a over the bytes.
+        values
+            .into_iter()
+            .chain(ships)
+            .flat_map(u32::to_le_bytes)
+            .fold(0x811c_9dc5, |hash, byte| {
+                (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+            })
+    }
+
     pub fn change_view(&mut self, pos: Vec2) {
         let pos = Vec2::new(pos.x - 80.0, pos.y - 80.0);
         self.view.change_pos(pos);
     }
 
     pub fn count_planets(&self) -> u8 {
-        self.planets
-            .iter()
-            .filter(|planet| planet.pos.distance(self.view.center()) < 114.0 * 5.0)
-            .count() as u8
+        let center = self.view.center();
+        let nearby = Circle::new(center, scalar(NEARBY)).bounds();
+        let count = self
+            .planet_cells
+            .within(self.planets.as_slice(), &nearby)
+            .filter(|planet| planet.pos.distance(center) < NEARBY)
+            .count();
+        u8::try_from(count).unwrap_or(u8::MAX)
     }
 
     pub fn count_stars(&self) -> u8 {
-        self.stars
-            .iter()
-            .filter(|star| star.pos.distance(self.view.center_mul(3.0)) < 114.0 * 5.0)
-            .count() as u8
+        let center = self.view.center_mul(3.0);
+        let nearby = Circle::new(center, scalar(NEARBY)).bounds();
+        let count = self
+            .star_cells
+            .within(self.stars.as_slice(), &nearby)
+            .filter(|star| star.pos.distance(center) < NEARBY)
+            .count();
+        u8::try_from(count).unwrap_or(u8::MAX)
     }
 
-    pub fn draw(&self) {
-        let view = &self.view;
-        self.stars
-            .as_slice()
-            .iter()
-            .filter(|star| star.pos.distance(self.view.center_mul(3.0)) < 114.0 * 3.0)
-            .for_each(|star| star.draw(view));
+    #[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+    pub fn draw(&self, settings: &Settings, screen: &mut Framebuffer) {
+        let shaken;
+        let view = if settings.shake && self.shake > 0 {
+            // Up to two pixels, flipping side every frame.
+            let amount = f32::from(self.shake.min(8)) / 4.0;
+            let amount = if self.frame.is_multiple_of(2) {
+                amount
+            } else {
+                -amount
+            };
+            shaken = self.view.offset(Vec2::from_f32(amount, -amount / 2.0));
+            &shaken
+        } else {
+            &self.view
+        };
+        let sky = Star::kept_for(view);
+        for star in self.star_cells.within(self.stars.as_slice(), &sky) {
+            star.draw(view, screen);
+        }
+        // With room for their air, which reaches half as far out again.
+        let space = view.grown(MAX_PLANET_RADIUS * 1.5);
+        for planet in self.planet_cells.within(self.planets.as_slice(), &space) {
+            planet.draw(view, screen);
+        }
+        let orbits = Station::kept_for(view);
+        let scanned = Circle::new(view.center(), self.upgrades.scanner_range());
+        for station in self.stations.as_slice() {
+            let pos = station.pos(self.frame);
+            if orbits.contains(pos) {
+                station.draw(view, self.frame);
+            } else if scanned.contains(pos) {
+                draw_edge_marker(view, pos, 2);
+            }
+        }
+        for particle in &self.particles {
+            particle.debug_draw(view, screen);
+        }
 
-        self.planets
-            .as_slice()
-            .iter()
-            .filter(|planet| planet.pos.distance(self.view.center()) < 114.0 + planet.radius)
-            .for_each(|planet| planet.draw(view));
+        if let Some(ref ghost) = self.ghost {
+            ghost.draw(view, screen);
+        }
 
-        self.particles
-            .iter()
-            .filter(|particle| particle.pos.distance(self.view.center()) < 114.0)
-            .for_each(|particle| particle.debug_draw(view));
+        wasm4::DrawColors::new().first(4).set();
 
-        unsafe {
-            *wasm4::DRAW_COLORS = 4;
+        let padding = 5;
+        if let Some(ref target) = self.target_planet {
+            let distance = self
+                .ships
+                .iter()
+                .map(|ship| target.pos.distance(ship.player.physics.pos))
+                .fold(INFINITY, Scalar::min);
+            let detector_size = map(to_f32(distance), 0.0, 1_000.0, 1.0, 150.0);
+            if settings.assist != Assist::Off {
+                wasm4::line(
+                    padding,
+                    160 - padding,
+                    padding + detector_size as i32,
+                    160 - padding,
+                );
+            }
+            if settings.assist == Assist::Arrow {
+                for ship in &self.ships {
+                    let player_pos = ship.player.physics.pos;
+                    if !target.body().contains(player_pos) {
+                        draw_arrow(player_pos - view.top_left, target.pos - player_pos);
+                    }
+                }
+                if !view.intersects_circle(&target.body()) {
+                    draw_edge_marker(view, target.pos, 3);
+                }
+            }
+        }
+        self.draw_gauges(padding);
+        if matches!(self.mode, Mode::TimeTrial | Mode::Race) {
+            wasm4::text(Outcome::TimeTrial { frames: self.frame }.describe(), 0, 0);
+        }
+        for (index, ship) in self.ships.iter().enumerate() {
+            ship.player.draw2(view, index);
         }
+        // Only one station menu fits; the first docked ship's.
+        let docked = self
+            .ships
+            .iter()
+            .enumerate()
+            .find_map(|(index, ship)| ship.dock.as_ref().map(|dock| (index, dock)));
+        if let Some((index, dock)) = docked {
+            self.draw_dock(index, dock);
+        }
+    }
 
-        let padding = 5;
-        if let Some(target) = &self.target_planet {
-            let player_pos = self.player.physics.pos;
-            let distance = target.pos.distance(player_pos);
-            let detector_size = map(distance, 0.0, 1_000.0, 1.0, 150.0);
+    /// The lines over the distance line: deliveries, or the leaderboard in versus, the fuel and
+    /// what is left of the best kept package.
+    fn draw_gauges(&self, padding: i32) {
+        if self.mode.has_targets() && self.lineup.is_versus(self.mode) {
+            self.draw_leaderboard();
+        } else if self.mode.has_targets() {
             wasm4::line(
                 padding,
-                160 - padding,
-                padding + detector_size as i32,
-                160 - padding,
+                160 - 2 * padding,
+                padding + i32::from(self.score % 150),
+                160 - 2 * padding,
+            );
+        }
+        if let Some(fuel) = self.fuel {
+            let gauge = (i32::from(fuel) * 150).div_euclid(self.tank().max(1).into());
+            wasm4::line(
+                padding,
+                160 - 3 * padding,
+                padding + gauge,
+                160 - 3 * padding,
+            );
+        }
+        let damage = self.ships.iter().map(|ship| ship.damage).min().unwrap_or(0);
+        if damage > 0 && self.target_planet.is_some() {
+            // What is left of the package, shrinking from 100 pixels.
+            wasm4::line(
+                padding,
+                160 - 4 * padding,
+                padding + i32::from(100 - damage),
+                160 - 4 * padding,
             );
         }
-        wasm4::line(
-            padding,
-            160 - 2 * padding,
-            padding + i32::from(self.score % 150),
-            160 - 2 * padding,
-        );
-        self.player.draw2(view);
     }
-}
-
-#[derive(Clone)]
-struct Planet {
-    pos: Vec2,
-    radius: f32,
-    color: u8,
-}
 
-impl Planet {
-    pub const fn new(pos: Vec2, radius: f32, color: u8) -> Self {
-        Self { pos, radius, color }
+    /// The menu of the station ship `index` is docked at, in a box edged in its colour.
+    #[allow(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn draw_dock(&self, index: usize, dock: &Dock) {
+        let station = &dock.station;
+        let (height, hint) = match station.service {
+            Service::Board => (66, "\u{80} TAKE  \u{81} LEAVE"),
+            Service::Depot => (66, "\u{81} LEAVE"),
+            Service::Shop => (118, "\u{80} BUY  \u{81} LEAVE"),
+        };
+        wasm4::DrawColors::new()
+            .first(1)
+            .second(Player::color(index))
+            .set();
+        wasm4::rect(8, 12, 144, height);
+        wasm4::DrawColors::new().first(4).set();
+        wasm4::text(station.service.name(), 16, 16);
+        match station.service {
+            Service::Board => {
+                let from = station.pos(self.frame);
+                let rows: Vec<String<16>, MAX_JOBS> = self
+                    .jobs(station)
+                    .iter()
+                    .filter_map(|&planet| self.planets.as_slice().get(planet))
+                    .map(|planet| {
+                        let mut text = String::new();
+                        text.push_str("TO ").ok();
+                        push_number(&mut text, to_f32(planet.pos.distance(from)) as u32);
+                        text.push_str(" PX").ok();
+                        text
+                    })
+                    .collect();
+                if rows.is_empty() {
+                    wasm4::text("NO JOBS", 16, 32);
+                } else {
+                    dock.menu.draw(&rows, 30);
+                }
+            }
+            Service::Depot => wasm4::text(
+                if self.fuel.is_some() {
+                    "TANK FILLED"
+                } else {
+                    "NO FUEL NEEDED"
+                },
+                16,
+                32,
+            ),
+            Service::Shop => self.draw_shop(&dock.menu),
+        }
+        wasm4::DrawColors::new().first(2).set();
+        wasm4::text(hint, 16, 12 + i32::try_from(height).unwrap_or(0) - 10);
+        wasm4::DrawColors::new().first(4).set();
     }
 
-    pub fn gravity(&self, distance: f32) -> f32 {
-        let distance_to_surface = distance - (self.radius - 2.0);
-        self.radius / (distance_to_surface * distance_to_surface)
+    /// Each upgrade with the price of its next level, and the level of the one picked.
+    fn draw_shop(&self, menu: &Menu) {
+        let rows: Vec<String<16>, { Upgrade::ALL.len() }> = Upgrade::ALL
+            .iter()
+            .map(|&upgrade| {
+                let mut text = String::new();
+                text.push_str(upgrade.name()).ok();
+                // Prices line up after the longest name.
+                while text.len() < 11 && text.push(' ').is_ok() {}
+                match self.upgrades.price(upgrade) {
+                    Some(price) => push_number(&mut text, price.into()),
+                    None => {
+                        text.push_str("MAX").ok();
+                    }
+                }
+                text
+            })
+            .collect();
+        menu.draw(&rows, 30);
+        wasm4::DrawColors::new().first(3).set();
+        if let Some(&upgrade) = Upgrade::ALL.get(menu.selected()) {
+            let mut level: String<16> = String::new();
+            level.push_str("LEVEL ").ok();
+            push_number(&mut level, self.upgrades.level(upgrade).into());
+            level.push_str(" OF ").ok();
+            push_number(&mut level, MAX_LEVEL.into());
+            wasm4::text(level, 16, 104);
+        }
+        let mut credits: String<16> = String::new();
+        credits.push_str("CREDITS ").ok();
+        push_number(&mut credits, self.upgrades.credits.into());
+        wasm4::text(credits, 16, 114);
     }
 
-    pub fn draw(&self, view: &Rect) {
-        let left = view.top_left.x;
-        let top = view.top_left.y;
-
-        for screen_y in 0..160u8 {
-            for screen_x in 0..160u8 {
-                let screen_point = Vec2::new(f32::from(screen_x) + left, f32::from(screen_y) + top);
-                let distance = self.pos.distance(screen_point);
-                if distance > self.radius {
-                    continue;
-                }
-                let color = map(distance, 0.0, self.radius, 0.0, f32::from(self.color));
-                draw_pixel(screen_x, screen_y, color as u8);
-            }
+    /// The standings down the top right, each ship in its colour with its points.
+    fn draw_leaderboard(&self) {
+        let mut y = 0;
+        for (index, stats) in self.standings() {
+            let mut text: String<8> = String::new();
+            text.push('P').ok();
+            push_number(&mut text, u32::try_from(index + 1).unwrap_or(0));
+            text.push(' ').ok();
+            push_number(&mut text, stats.score.into());
+            wasm4::DrawColors::new().first(Player::color(index)).set();
+            wasm4::text(text, 112, y);
+            y += 8;
         }
+        wasm4::DrawColors::new().first(4).set();
     }
 }
 
+/// A short line in `color` on the edge of the screen, where the way from its middle to the
+/// off-screen `target` leaves it.
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+fn draw_edge_marker(view: &Rect, target: Vec2, color: u8) {
+    let center = view.center();
+    let Some(ray) = Ray::new(center, target - center) else {
+        return;
+    };
+    // Kept off the last pixel so the whole line shows.
+    let Some((_, out)) = ray.cast_rect(&view.grown(-2.0)) else {
+        return;
+    };
+    let end = ray.at(out) - view.top_left;
+    let start = end - ray.direction() * 4.0;
+    wasm4::DrawColors::new().first(color).set();
+    line(start, end);
+    wasm4::DrawColors::new().first(4).set();
+}
+
+/// A short line pointing from just outside the ship along `direction`.
+#[allow(clippy::as_conversions, clippy::cast_possible_truncation)]
+fn draw_arrow(ship: Vec2, direction: Vec2) {
+    let direction = direction.normalized();
+    let ship = ship - Vec2::from_f32(0.0, 3.0);
+    let start = ship + direction * 8.0;
+    let end = ship + direction * 14.0;
+    wasm4::DrawColors::new().first(3).set();
+    line(start, end);
+    wasm4::DrawColors::new().first(4).set();
+}
+
 struct Star {
     pos: Vec2,
     color: u8,
-    size: f32,
+    size: Scalar,
 }
 
 impl Star {
-    pub const fn new(pos: Vec2, color: u8, size: f32) -> Self {
+    pub const fn new(pos: Vec2, color: u8, size: Scalar) -> Self {
         Self { pos, color, size }
     }
 
-    pub fn draw(&self, view: &Rect) {
-        let left = view.top_left.x / 3.0;
-        let top = view.top_left.y / 3.0;
+    /// Stars are three times as far as planets, so they move a third as fast across the screen.
+    fn parallax(view: &Rect) -> Rect {
+        Rect::new(view.top_left / 3.0, view.size)
+    }
+
+    /// Where the stars that might show in `view` are kept, before they are brought in.
+    fn kept_for(view: &Rect) -> Rect {
+        Rect::new(view.top_left, view.size * 3.0).grown(MAX_STAR_SIZE * 3.0)
+    }
 
-        for screen_y in 0..160u8 {
-            for screen_x in 0..160u8 {
-                let screen_point = Vec2::new(f32::from(screen_x) + left, f32::from(screen_y) + top);
-                let distance = (self.pos * (1. / 3.)).distance(screen_point);
-                if distance > self.size {
-                    continue;
-                }
-                draw_pixel(screen_x, screen_y, self.color);
-            }
-        }
+    fn body(&self) -> Circle {
+        Circle::new(self.pos * (1. / 3.), self.size)
+    }
+
+    pub fn draw(&self, view: &Rect, screen: &mut Framebuffer) {
+        draw_disc(screen, &Self::parallax(view), &self.body(), &|_| self.color);
     }
 }
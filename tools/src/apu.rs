@@ -0,0 +1,221 @@
+// The WASM-4 sound chip in software, following the reference runtime's APU: two pulse
+// channels with four duty cycles, a triangle, and a noise channel driven by a xorshift
+// generator, each with a linear attack, decay, sustain and release envelope.
+
+use crate::wasm4::Tone;
+use std::io::{self, Write};
+
+pub const SAMPLE_RATE: u32 = 44_100;
+pub const SAMPLES_PER_FRAME: u32 = SAMPLE_RATE / 60;
+// Loudest a channel gets, about 15% of the 16-bit range; the triangle is quieter to the ear.
+const MAX_VOLUME: f32 = 4915.0;
+const MAX_VOLUME_TRIANGLE: f32 = 8192.0;
+
+#[derive(Clone, Copy, Default)]
+struct Channel {
+    first: f32,
+    last: f32,
+    start: u64,
+    attack: u64,
+    decay: u64,
+    sustain: u64,
+    release: u64,
+    peak: f32,
+    volume: f32,
+    duty: f32,
+    phase: f32,
+    seed: u16,
+    random: f32,
+}
+
+impl Channel {
+    fn frequency(&self, time: u64) -> f32 {
+        if self.last > 0.0 {
+            ramp(self.first, self.last, self.start, self.release, time)
+        } else {
+            self.first
+        }
+    }
+
+    fn envelope(&self, time: u64) -> f32 {
+        if time >= self.sustain {
+            ramp(self.volume, 0.0, self.sustain, self.release, time)
+        } else if time >= self.decay {
+            self.volume
+        } else if time >= self.attack {
+            ramp(self.peak, self.volume, self.attack, self.decay, time)
+        } else {
+            ramp(0.0, self.peak, self.start, self.attack, time)
+        }
+    }
+}
+
+fn ramp(from: f32, to: f32, start: u64, end: u64, time: u64) -> f32 {
+    if end <= start || time >= end {
+        return to;
+    }
+    let t = (time - start) as f32 / (end - start) as f32;
+    from + (to - from) * t
+}
+
+pub struct Apu {
+    channels: [Channel; 4],
+    // Samples rendered so far.
+    time: u64,
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Apu {
+    pub fn new() -> Self {
+        let mut channels = [Channel::default(); 4];
+        channels[3].seed = 1;
+        Self { channels, time: 0 }
+    }
+
+    /// Starts `tone` now, replacing whatever its channel was playing.
+    pub fn tone(&mut self, tone: &Tone) {
+        let frames =
+            |shift: u32| u64::from((tone.duration >> shift) & 0xff) * u64::from(SAMPLES_PER_FRAME);
+        let percent = |shift: u32| ((tone.volume >> shift) & 0xff).min(100) as f32 / 100.0;
+        let index = (tone.flags & 0x3) as usize;
+        let channel = &mut self.channels[index];
+        let max = if index == 2 {
+            MAX_VOLUME_TRIANGLE
+        } else {
+            MAX_VOLUME
+        };
+
+        channel.first = (tone.frequency & 0xffff) as f32;
+        channel.last = (tone.frequency >> 16) as f32;
+        channel.start = self.time;
+        channel.attack = channel.start + frames(24);
+        channel.decay = channel.attack + frames(16);
+        channel.sustain = channel.decay + frames(0);
+        channel.release = channel.sustain + frames(8);
+        channel.volume = percent(0) * max;
+        // A peak of zero means full volume.
+        channel.peak = if (tone.volume >> 8) & 0xff == 0 {
+            max
+        } else {
+            percent(8) * max
+        };
+        channel.duty = match (tone.flags >> 2) & 0x3 {
+            0 => 0.125,
+            1 => 0.25,
+            2 => 0.5,
+            _ => 0.75,
+        };
+    }
+
+    /// Mixes the next `count` samples into `out`.
+    pub fn render(&mut self, count: u32, out: &mut Vec<i16>) {
+        for _ in 0..count {
+            let time = self.time;
+            let mut mix = 0.0;
+            for (index, channel) in self.channels.iter_mut().enumerate() {
+                if time >= channel.release {
+                    continue;
+                }
+                let frequency = channel.frequency(time);
+                let volume = channel.envelope(time);
+                mix += match index {
+                    0 | 1 => {
+                        channel.phase = (channel.phase + frequency / SAMPLE_RATE as f32).fract();
+                        if channel.phase < channel.duty {
+                            volume
+                        } else {
+                            -volume
+                        }
+                    }
+                    2 => {
+                        channel.phase = (channel.phase + frequency / SAMPLE_RATE as f32).fract();
+                        volume * (4.0 * (channel.phase - 0.5).abs() - 1.0)
+                    }
+                    _ => {
+                        // Higher "frequencies" clock the generator faster, quadratically.
+                        channel.phase += frequency * frequency / 1_000_000.0;
+                        while channel.phase > 0.0 {
+                            channel.phase -= 1.0;
+                            channel.seed ^= channel.seed >> 7;
+                            channel.seed ^= channel.seed << 9;
+                            channel.seed ^= channel.seed >> 13;
+                            channel.random = if channel.seed & 1 == 0 { -1.0 } else { 1.0 };
+                        }
+                        volume * channel.random
+                    }
+                };
+            }
+            out.push(mix.clamp(f32::from(i16::MIN), f32::from(i16::MAX)) as i16);
+            self.time += 1;
+        }
+    }
+}
+
+/// Plays `tones` frame by frame for `frames` frames.
+pub fn render(tones: &[Tone], frames: u32) -> Vec<i16> {
+    let mut apu = Apu::new();
+    let mut samples = Vec::with_capacity((frames * SAMPLES_PER_FRAME) as usize);
+    let mut pending = tones.iter().peekable();
+    for frame in 0..frames {
+        while let Some(tone) = pending.next_if(|tone| tone.frame <= frame) {
+            apu.tone(tone);
+        }
+        apu.render(SAMPLES_PER_FRAME, &mut samples);
+    }
+    samples
+}
+
+/// 16-bit mono PCM.
+pub fn write_wav(out: &mut impl Write, samples: &[i16]) -> io::Result<()> {
+    let data = u32::try_from(samples.len() * 2).unwrap_or(u32::MAX);
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data.to_le_bytes())?;
+    for sample in samples {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// One line per tone, stable enough to diff between builds.
+pub fn write_log(out: &mut impl Write, tones: &[Tone]) -> io::Result<()> {
+    const CHANNELS: [&str; 4] = ["pulse1", "pulse2", "triangle", "noise"];
+    const DUTIES: [&str; 4] = ["12", "25", "50", "75"];
+    writeln!(
+        out,
+        "# frame channel from to attack decay sustain release peak volume duty"
+    )?;
+    for tone in tones {
+        let byte = |value: u32, shift: u32| (value >> shift) & 0xff;
+        writeln!(
+            out,
+            "{} {} {} {} {} {} {} {} {} {} {}",
+            tone.frame,
+            CHANNELS[(tone.flags & 0x3) as usize],
+            tone.frequency & 0xffff,
+            tone.frequency >> 16,
+            byte(tone.duration, 24),
+            byte(tone.duration, 16),
+            byte(tone.duration, 0),
+            byte(tone.duration, 8),
+            byte(tone.volume, 8),
+            byte(tone.volume, 0),
+            DUTIES[((tone.flags >> 2) & 0x3) as usize],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+// Measures how far the game's `math` functions are from `libm`'s and fails if any of them is
+// further off than it should be. The checks are in `src/matherr.rs`.
+//
+//     cargo run --bin matherr
+//     cargo run --bin matherr --features fast-math
+
+use space_delivery_tools::matherr::{measure, CHECKS, SAMPLES};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let fast = cfg!(feature = "fast-math");
+    println!(
+        "{} math, {SAMPLES} points each",
+        if fast { "fast" } else { "accurate" }
+    );
+    println!(
+        "{:<14}{:>24}{:>14}{:>14}{:>16}",
+        "function", "range", "max error", "bound", "worst at"
+    );
+    let mut failed = 0;
+    for check in CHECKS {
+        let (worst, at) = measure(check);
+        let bound = check.bound();
+        let over = worst > bound;
+        if over {
+            failed += 1;
+        }
+        println!(
+            "{:<14}{:>24}{:>14.3e}{:>14.1e}{:>16}{}",
+            check.name,
+            format!("{} .. {}", check.range.0, check.range.1),
+            worst,
+            bound,
+            at,
+            if over { "  TOO FAR OFF" } else { "" }
+        );
+    }
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("matherr: {failed} of {} checks too far off", CHECKS.len());
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,132 @@
+// Plays the game's music or a sound effect without the WASM-4 runtime and writes
+// what it would have sounded like.
+//
+//     cargo run --bin render -- music/intro.song --wav intro.wav --log intro.tones
+//     cargo run --bin render -- --composer gravity-well --seed 3 --frames 1200 --wav well.wav
+//     cargo run --bin render -- --sfx delivery --wav delivery.wav
+//
+// `--log` writes every `tone` call with its frame, which makes timing changes show up in a diff.
+
+use space_delivery_tools::composer::Mood;
+use space_delivery_tools::sfx::Sound;
+use space_delivery_tools::{apu, play};
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: render [--frames N] [--wav PATH] [--log PATH] \
+    (SONG.song | SONG.mid | --composer MOOD [--seed N] | --sfx SOUND)";
+
+enum Source {
+    Song(String),
+    Composer(Mood),
+    Sfx(Sound),
+}
+
+struct Options {
+    source: Source,
+    frames: Option<u32>,
+    seed: u64,
+    wav: Option<String>,
+    log: Option<String>,
+}
+
+fn main() -> ExitCode {
+    match parse_args(std::env::args().skip(1)).and_then(run) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("render: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut source = None;
+    let mut frames = None;
+    let mut seed = 0;
+    let mut wav = None;
+    let mut log = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{arg}` needs a value\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--frames" => frames = Some(value()?.parse().map_err(|_| "--frames takes a number")?),
+            "--seed" => seed = value()?.parse().map_err(|_| "--seed takes a number")?,
+            "--wav" => wav = Some(value()?),
+            "--log" => log = Some(value()?),
+            "--composer" => source = Some(Source::Composer(mood(&value()?)?)),
+            "--sfx" => source = Some(Source::Sfx(sound(&value()?)?)),
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`\n{USAGE}")),
+            _ => source = Some(Source::Song(arg)),
+        }
+    }
+    Ok(Options {
+        source: source.ok_or(USAGE)?,
+        frames,
+        seed,
+        wav,
+        log,
+    })
+}
+
+fn mood(name: &str) -> Result<Mood, String> {
+    Ok(match name {
+        "cruising" => Mood::Cruising,
+        "gravity-well" => Mood::GravityWell,
+        "near-target" => Mood::NearTarget,
+        "delivered" => Mood::Delivered,
+        _ => {
+            return Err(format!(
+                "unknown mood `{name}`, try cruising, gravity-well, near-target or delivered"
+            ))
+        }
+    })
+}
+
+fn sound(name: &str) -> Result<Sound, String> {
+    Ok(match name {
+        "thrust" => Sound::Thrust,
+        "boost" => Sound::Boost,
+        "collision" => Sound::Collision,
+        "pickup" => Sound::Pickup,
+        "delivery" => Sound::Delivery,
+        "ui" => Sound::Ui,
+        _ => {
+            return Err(format!(
+                "unknown sound `{name}`, try thrust, boost, collision, pickup, delivery or ui"
+            ))
+        }
+    })
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let (tones, frames) = match options.source {
+        Source::Song(path) => play::song(play::load_song(&path)?, options.frames),
+        Source::Composer(mood) => {
+            let frames = options.frames.unwrap_or(600);
+            (play::composer(mood, options.seed, frames), frames)
+        }
+        Source::Sfx(sound) => {
+            let frames = options.frames.unwrap_or(120);
+            (play::sfx(sound, frames), frames)
+        }
+    };
+
+    eprintln!("render: {} tones over {frames} frames", tones.len());
+    if let Some(path) = options.log {
+        let mut out =
+            BufWriter::new(File::create(&path).map_err(|error| format!("{path}: {error}"))?);
+        apu::write_log(&mut out, &tones).map_err(|error| format!("{path}: {error}"))?;
+    }
+    if let Some(path) = options.wav {
+        let samples = apu::render(&tones, frames);
+        let mut out =
+            BufWriter::new(File::create(&path).map_err(|error| format!("{path}: {error}"))?);
+        apu::write_wav(&mut out, &samples).map_err(|error| format!("{path}: {error}"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+// Runs a recorded session through the game's world without drawing anything and checks
+// that it ends in the same state as when it was recorded.
+//
+//     cargo run --bin replay -- bug.txt
+//     cargo run --bin replay -- bug.txt --every 60 > states.txt
+//     cargo run --bin replay -- bug.txt --write bug.sdr
+//
+// The input is either the `replay: ` lines the game traces on a right click, or raw bytes.
+
+use space_delivery_tools::input::Playback;
+use space_delivery_tools::{replay, rerun};
+use std::fs;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: replay FILE [--every FRAMES] [--write PATH]";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1)) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("replay: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns whether the replay ended where the recording did.
+fn run(mut args: impl Iterator<Item = String>) -> Result<bool, String> {
+    let mut path = None;
+    let mut every = None;
+    let mut write = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{arg}` needs a value\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--every" => {
+                every = Some(
+                    value()?
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&every| every > 0)
+                        .ok_or("--every takes a number of frames")?,
+                );
+            }
+            "--write" => write = Some(value()?),
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`\n{USAGE}")),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.ok_or(USAGE)?;
+    let contents = fs::read(&path).map_err(|error| format!("{path}: {error}"))?;
+    let replay = replay::read(&contents).map_err(|error| format!("{path}: {error}"))?;
+    if let Some(out) = write {
+        fs::write(&out, &replay.data).map_err(|error| format!("{out}: {error}"))?;
+    }
+
+    let playback = Playback::parse(&replay.data).map_err(|error| error.message().to_owned())?;
+    let rerun = rerun::rerun(playback, |world| {
+        if every.is_some_and(|every| world.frame().is_multiple_of(every)) {
+            println!("{} {:08x}", world.frame(), world.checksum());
+        }
+    });
+    if let Some(outcome) = rerun.outcome {
+        println!("run over: {}", outcome.describe());
+    }
+    let (frames, checksum) = (rerun.frames, rerun.checksum);
+    println!("{frames} frames, checksum {checksum:08x}");
+    match replay.end {
+        Some((expected_frames, expected)) if (expected_frames, expected) != (frames, checksum) => {
+            eprintln!(
+                "replay: diverged, the recording ended after {expected_frames} frames with checksum {expected:08x}"
+            );
+            Ok(false)
+        }
+        Some(_) => {
+            println!("matches the recording");
+            Ok(true)
+        }
+        None => Ok(true),
+    }
+}
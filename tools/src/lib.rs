@@ -0,0 +1,74 @@
+// The game's code compiled for the host, with `wasm4` swapped for a recorder.
+// Lints on the shared files are dealt with in the game crate.
+#![allow(
+    dead_code,
+    unused_variables,
+    clippy::new_without_default,
+    clippy::get_first,
+    clippy::unnecessary_cast
+)]
+
+pub mod apu;
+pub mod matherr;
+pub mod play;
+pub mod rerun;
+pub mod shelved;
+pub mod wasm4;
+
+#[path = "../../src/arrangement.rs"]
+pub mod arrangement;
+#[path = "../../src/common.rs"]
+pub mod common;
+#[path = "../../src/composer.rs"]
+pub mod composer;
+#[path = "../../src/fixed.rs"]
+pub mod fixed;
+#[path = "../../src/geometry.rs"]
+pub mod geometry;
+#[path = "../../src/gfx.rs"]
+pub mod gfx;
+#[path = "../../src/ghost.rs"]
+pub mod ghost;
+#[path = "../../src/grid.rs"]
+pub mod grid;
+#[path = "../../src/input.rs"]
+pub mod input;
+#[path = "../../src/math.rs"]
+pub mod math;
+#[path = "../../src/menu.rs"]
+pub mod menu;
+#[path = "../../src/mode.rs"]
+pub mod mode;
+#[path = "../../src/palette.rs"]
+pub mod palette;
+#[path = "../../src/particle.rs"]
+pub mod particle;
+#[path = "../../src/planet.rs"]
+pub mod planet;
+#[path = "../../src/player.rs"]
+pub mod player;
+#[path = "../../src/ring.rs"]
+pub mod ring;
+#[path = "../../src/scalar.rs"]
+pub mod scalar;
+#[path = "../../src/settings.rs"]
+pub mod settings;
+#[path = "../../src/sfx.rs"]
+pub mod sfx;
+#[path = "../../src/song.rs"]
+pub mod song;
+#[path = "../../src/station.rs"]
+pub mod station;
+#[path = "../../src/theory.rs"]
+pub mod theory;
+#[path = "../../src/upgrade.rs"]
+pub mod upgrade;
+#[path = "../../src/world.rs"]
+pub mod world;
+
+#[path = "../../build/midi.rs"]
+pub mod midi;
+#[path = "../../build/replay.rs"]
+pub mod replay;
+#[path = "../../build/songc.rs"]
+pub mod songc;
@@ -0,0 +1,171 @@
+// How far the game's `math` functions are from `libm`'s, which rounds correctly in `f64`, and
+// how far they may be. Errors are absolute where the result is an angle or bounded by one,
+// relative otherwise.
+
+use crate::{math, shelved};
+
+// Points per function, spread evenly over its range.
+pub const SAMPLES: u32 = 200_000;
+
+#[derive(Clone, Copy)]
+pub enum Error {
+    Absolute,
+    Relative,
+}
+
+pub struct Check {
+    pub name: &'static str,
+    pub range: (f32, f32),
+    pub error: Error,
+    // The accurate and the fast versions' bounds.
+    bounds: (f64, f64),
+    pub game: fn(f32) -> f32,
+    pub host: fn(f64) -> f64,
+}
+
+pub const CHECKS: &[Check] = &[
+    Check {
+        name: "sqrt",
+        range: (0.0, 1.0e6),
+        error: Error::Relative,
+        bounds: (1.0e-6, 0.07),
+        game: math::sqrt,
+        host: libm::sqrt,
+    },
+    Check {
+        name: "sqrt",
+        range: (1.0e-6, 1.0),
+        error: Error::Relative,
+        bounds: (1.0e-6, 0.07),
+        game: math::sqrt,
+        host: libm::sqrt,
+    },
+    Check {
+        name: "sin",
+        range: (-100.0, 100.0),
+        error: Error::Absolute,
+        bounds: (1.0e-6, 0.002),
+        game: math::sin,
+        host: libm::sin,
+    },
+    Check {
+        name: "cos",
+        range: (-100.0, 100.0),
+        error: Error::Absolute,
+        bounds: (1.0e-6, 0.002),
+        game: math::cos,
+        host: libm::cos,
+    },
+    Check {
+        name: "atan2(y, 1)",
+        range: (-100.0, 100.0),
+        error: Error::Absolute,
+        bounds: (1.0e-6, 0.005),
+        game: |y| shelved::atan2(y, 1.0),
+        host: |y| libm::atan2(y, 1.0),
+    },
+    Check {
+        name: "atan2(1, x)",
+        range: (-100.0, 100.0),
+        error: Error::Absolute,
+        bounds: (1.0e-6, 0.005),
+        game: |x| shelved::atan2(1.0, x),
+        host: |x| libm::atan2(1.0, x),
+    },
+    Check {
+        name: "atan2(-1, x)",
+        range: (-100.0, 100.0),
+        error: Error::Absolute,
+        bounds: (1.0e-6, 0.005),
+        game: |x| shelved::atan2(-1.0, x),
+        host: |x| libm::atan2(-1.0, x),
+    },
+    Check {
+        name: "exp",
+        range: (-80.0, 80.0),
+        error: Error::Relative,
+        bounds: (1.0e-6, 0.04),
+        game: shelved::exp,
+        host: libm::exp,
+    },
+    Check {
+        name: "powf(x, 1.5)",
+        range: (0.0, 1000.0),
+        error: Error::Relative,
+        bounds: (2.0e-6, 0.1),
+        game: |x| shelved::powf(x, 1.5),
+        host: |x| libm::pow(x, 1.5),
+    },
+    Check {
+        name: "powf(2, x)",
+        range: (-60.0, 60.0),
+        error: Error::Relative,
+        // `exp` multiplies the rounding in `power * ln base` by up to 40 here.
+        bounds: (4.0e-6, 0.1),
+        game: |x| shelved::powf(2.0, x),
+        host: |x| libm::pow(2.0, x),
+    },
+    Check {
+        name: "powf(-x, 3)",
+        range: (0.0, 100.0),
+        error: Error::Relative,
+        bounds: (2.0e-6, 0.1),
+        game: |x| shelved::powf(-x, 3.0),
+        host: |x| libm::pow(-x, 3.0),
+    },
+    Check {
+        name: "floor",
+        range: (-1.0e9, 1.0e9),
+        error: Error::Absolute,
+        bounds: (0.0, 0.0),
+        game: math::floor,
+        host: libm::floor,
+    },
+    Check {
+        name: "floor",
+        range: (-10.0, 10.0),
+        error: Error::Absolute,
+        bounds: (0.0, 0.0),
+        game: math::floor,
+        host: libm::floor,
+    },
+];
+
+impl Check {
+    /// The largest error allowed for the `math` this was built with.
+    pub const fn bound(&self) -> f64 {
+        if cfg!(feature = "fast-math") {
+            self.bounds.1
+        } else {
+            self.bounds.0
+        }
+    }
+}
+
+/// The largest error over the check's range, and where it was.
+pub fn measure(check: &Check) -> (f64, f32) {
+    let (low, high) = check.range;
+    let mut worst = (0.0, low);
+    for step in 0..=SAMPLES {
+        let x = low + (high - low) * (step as f32 / SAMPLES as f32);
+        let got = f64::from((check.game)(x));
+        let want = (check.host)(f64::from(x));
+        let error = match check.error {
+            Error::Absolute => (got - want).abs(),
+            // The floor only keeps an exact zero from dividing by zero.
+            Error::Relative => (got - want).abs() / want.abs().max(f64::from(f32::MIN_POSITIVE)),
+        };
+        // A NaN where there should be none is as wrong as it gets.
+        let error = if error.is_nan() && !want.is_nan() {
+            f64::INFINITY
+        } else if error.is_nan() {
+            0.0
+        } else {
+            error
+        };
+        if error > worst.0 {
+            worst = (error, x);
+        }
+    }
+    worst
+}
@@ -0,0 +1,87 @@
+// Runs the game's music and sound effects frame by frame, as the cart does, and collects the
+// `tone` calls they make. `render` and the tests share these.
+
+use crate::arrangement::{Arrangement, Channel, Wave};
+use crate::composer::{Composer, Mood};
+use crate::sfx::{Sfx, Sound};
+use crate::song::{Song, SongPlayer};
+use crate::wasm4::{self, Tone};
+use crate::{midi, songc};
+use std::fs;
+
+// Lets the last release ring out after a song ends.
+const TAIL_FRAMES: u32 = 30;
+
+/// The same channels `src/game.rs` plays music on.
+fn music() -> Arrangement {
+    Arrangement::new(
+        Some(Channel::new(Wave::Triangle)),
+        Some(Channel::new(Wave::Pulse1)),
+        Some(Channel::new(Wave::Pulse2)),
+        Some(Channel::new(Wave::Noise)),
+    )
+}
+
+/// Plays `song` to the end, or for `frames` frames. Returns its tones and how many frames they
+/// take to ring out.
+pub fn song(song: Song<'static>, frames: Option<u32>) -> (Vec<Tone>, u32) {
+    let mut music = music();
+    let mut player = SongPlayer::new(song, false);
+    let limit = frames.unwrap_or(u32::MAX);
+    let mut frame = 0;
+    while frame < limit {
+        wasm4::set_frame(frame);
+        if !player.update(&mut music) {
+            break;
+        }
+        music.update();
+        frame += 1;
+    }
+    (wasm4::take_tones(), frames.unwrap_or(frame + TAIL_FRAMES))
+}
+
+/// What the composer plays in `mood` for `frames` frames, from `seed`.
+pub fn composer(mood: Mood, seed: u64, frames: u32) -> Vec<Tone> {
+    let mut music = music();
+    let mut composer = Composer::new();
+    let mut random = oorandom::Rand32::new(seed);
+    composer.set_mood(mood);
+    for frame in 0..frames {
+        wasm4::set_frame(frame);
+        composer.compose(&mut music, &mut random);
+        music.update();
+    }
+    wasm4::take_tones()
+}
+
+/// `sound` played once, over `frames` frames.
+pub fn sfx(sound: Sound, frames: u32) -> Vec<Tone> {
+    let mut music = music();
+    let mut sfx = Sfx::new();
+    sfx.play(sound);
+    for frame in 0..frames {
+        wasm4::set_frame(frame);
+        sfx.update(&mut music);
+        music.update();
+    }
+    wasm4::take_tones()
+}
+
+/// A `.song` file, or a `.mid` file converted on the way, printing what did not convert.
+pub fn load_song(path: &str) -> Result<Song<'static>, String> {
+    let source = if path.ends_with(".mid") {
+        let data = fs::read(path).map_err(|error| format!("{path}: {error}"))?;
+        let conversion = midi::convert(&data).map_err(|error| format!("{path}: {error}"))?;
+        for warning in &conversion.warnings {
+            eprintln!("{path}: {warning}");
+        }
+        conversion.source
+    } else {
+        fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?
+    };
+    let data = songc::compile(&source)
+        .map_err(|error| format!("{path}:{}: {}", error.line, error.message))?;
+    // The player wants a song that lives as long as the program, like the embedded ones.
+    let data: &'static [u8] = Box::leak(data.into_boxed_slice());
+    Song::parse(data).map_err(|error| format!("{path}: {}", error.message()))
+}
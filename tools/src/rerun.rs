@@ -0,0 +1,40 @@
+// Plays a recording back through the game's world, the way the cart does, without drawing.
+// `replay` and the tests share this.
+
+use crate::input::Playback;
+use crate::mode::Outcome;
+use crate::sfx::Sfx;
+use crate::wasm4;
+use crate::world::World;
+
+/// Where a replay ended.
+pub struct Rerun {
+    pub frames: u32,
+    pub checksum: u32,
+    /// How the run ended, if it did before the recording ran out.
+    pub outcome: Option<Outcome>,
+}
+
+/// Runs `playback` to its end, calling `each_frame` after every update.
+pub fn rerun(playback: Playback<'_>, mut each_frame: impl FnMut(&World)) -> Rerun {
+    let mut world = World::new();
+    let mut sfx = Sfx::new();
+    world.set_seed(playback.seed());
+    world.set_upgrades(*playback.upgrades());
+    world.start(playback.mode(), playback.lineup());
+    for input in playback {
+        world.update(input, &mut sfx);
+        each_frame(&world);
+        // The game stops updating a run once it is over, and so stops recording.
+        if world.outcome().is_some() {
+            break;
+        }
+    }
+    // Sound effects were played into the recorder; nobody listens here.
+    drop(wasm4::take_tones());
+    Rerun {
+        frames: world.frame(),
+        checksum: world.checksum(),
+        outcome: world.outcome(),
+    }
+}
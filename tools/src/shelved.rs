@@ -0,0 +1,190 @@
+// `atan2`, `exp` and `powf` written the way `src/math.rs` is, for when the game needs them.
+// Nothing in the game measures an angle or raises to a power yet, so they live here rather than
+// take up room in the cart; move them back next to `sqrt` when something does. `matherr` checks
+// them with the rest.
+
+// Exact comparisons are deliberate here: they pick out special values, not near ones.
+#![allow(
+    clippy::unreadable_literal,
+    clippy::excessive_precision,
+    clippy::float_cmp
+)]
+
+use crate::math::floor;
+#[cfg(not(feature = "fast-math"))]
+use crate::math::round;
+use core::f32::consts::{FRAC_PI_2, PI};
+
+// ln 2 split into parts with enough trailing zeros that multiplying one by a small whole number
+// is exact.
+#[cfg(not(feature = "fast-math"))]
+const LN_2_HIGH: f32 = 0.693_145_75;
+#[cfg(not(feature = "fast-math"))]
+const LN_2_LOW: f32 = 1.428_606_8e-6;
+// Past these `exp` is infinite or zero.
+const EXP_MAX: f32 = 88.722_84;
+#[cfg(not(feature = "fast-math"))]
+const EXP_MIN: f32 = -103.972_08;
+
+#[cfg(not(feature = "fast-math"))]
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn exp(value: f32) -> f32 {
+    if value.is_nan() {
+        return value;
+    }
+    if value > EXP_MAX {
+        return f32::INFINITY;
+    }
+    if value < EXP_MIN {
+        return 0.0;
+    }
+    // e^value = 2^k * e^r with |r| at most ln 2 / 2.
+    let k = round(value / core::f32::consts::LN_2);
+    let r = value - k * LN_2_HIGH - k * LN_2_LOW;
+    let series = 1.0
+        + r * (1.0
+            + r * (0.5
+                + r * (1.0 / 6.0 + r * (1.0 / 24.0 + r * (1.0 / 120.0 + r * (1.0 / 720.0))))));
+    scale_by_power_of_two(series, k as i32)
+}
+
+/// Schraudolph's trick: write `value / ln 2` straight into the exponent. Within 4%.
+#[cfg(feature = "fast-math")]
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn exp(value: f32) -> f32 {
+    if value > EXP_MAX {
+        return f32::INFINITY;
+    }
+    if value < -87.0 {
+        return 0.0;
+    }
+    f32::from_bits((12_102_203.0 * value + 1_064_866_800.0) as u32)
+}
+
+/// `value * 2^power`, going through two steps so neither factor overflows on its own.
+#[cfg(not(feature = "fast-math"))]
+#[allow(clippy::as_conversions, clippy::cast_sign_loss)]
+fn scale_by_power_of_two(value: f32, power: i32) -> f32 {
+    let factor = |power: i32| f32::from_bits(((power + 127) as u32) << 23);
+    let half = power.div_euclid(2);
+    value * factor(half) * factor(power - half)
+}
+
+/// Natural logarithm, for `powf`. There is no fast version: its error would be multiplied
+/// by the power.
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_wrap
+)]
+fn ln(value: f32) -> f32 {
+    // Subnormals have no hidden bit; scale them up to where the exponent means something.
+    let (value, offset) = if value < f32::MIN_POSITIVE {
+        (value * 8_388_608.0, -23)
+    } else {
+        (value, 0)
+    };
+    // value = m * 2^e with m between √½ and √2, so ln value = e ln 2 + ln m.
+    let bits = value.to_bits();
+    let mut exponent = ((bits >> 23) & 0xff) as i32 - 127 + offset;
+    let mut mantissa = f32::from_bits(bits & 0x007f_ffff | 0x3f80_0000);
+    if mantissa > core::f32::consts::SQRT_2 {
+        mantissa *= 0.5;
+        exponent += 1;
+    }
+    // ln m = 2 atanh s, with s at most 0.172 the series converges fast.
+    let s = (mantissa - 1.0) / (mantissa + 1.0);
+    let z = s * s;
+    let series =
+        2.0 * s * (1.0 + z * (1.0 / 3.0 + z * (1.0 / 5.0 + z * (1.0 / 7.0 + z * (1.0 / 9.0)))));
+    exponent as f32 * core::f32::consts::LN_2 + series
+}
+
+/// `base` to the power `power`. A negative base only has real powers that are whole.
+pub fn powf(base: f32, power: f32) -> f32 {
+    if power == 0.0 || base == 1.0 {
+        return 1.0;
+    }
+    if base.is_nan() || power.is_nan() {
+        return f32::NAN;
+    }
+    if base == 0.0 {
+        return if power > 0.0 { 0.0 } else { f32::INFINITY };
+    }
+    let magnitude = if base.is_infinite() {
+        if power > 0.0 {
+            f32::INFINITY
+        } else {
+            0.0
+        }
+    } else {
+        exp(power * ln(base.abs()))
+    };
+    if base > 0.0 {
+        magnitude
+    } else if floor(power) != power {
+        f32::NAN
+    } else if floor(power * 0.5) == power * 0.5 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+/// Arctangent of `x` between -1 and 1.
+#[cfg(not(feature = "fast-math"))]
+fn atan_kernel(x: f32) -> f32 {
+    // atan x = ±π/4 + atan((x ∓ 1) / (1 + |x|)) brings everything within tan(π/8), where
+    // the series is good to 2e-8 by the x^15 term.
+    const TAN_FRAC_PI_8: f32 = 0.414_213_57;
+    let (x, offset) = if x.abs() > TAN_FRAC_PI_8 {
+        let sign = 1.0_f32.copysign(x);
+        ((x - sign) / (1.0 + x.abs()), sign * (PI / 4.0))
+    } else {
+        (x, 0.0)
+    };
+    let z = x * x;
+    let series = 1.0
+        + z * (-1.0 / 3.0
+            + z * (1.0 / 5.0
+                + z * (-1.0 / 7.0
+                    + z * (1.0 / 9.0 + z * (-1.0 / 11.0 + z * (1.0 / 13.0 - z / 15.0))))));
+    offset + x * series
+}
+
+/// A curve through atan at -1, 0 and 1, within 0.002.
+#[cfg(feature = "fast-math")]
+fn atan_kernel(x: f32) -> f32 {
+    PI / 4.0 * x - x * (x.abs() - 1.0) * (0.2447 + 0.0663 * x.abs())
+}
+
+/// The angle from the positive x axis to (x, y), between -π and π.
+pub fn atan2(y: f32, x: f32) -> f32 {
+    if x.is_nan() || y.is_nan() {
+        return f32::NAN;
+    }
+    if x == 0.0 && y == 0.0 {
+        // The signs of the zeros pick the side, as in libm.
+        return if x.is_sign_negative() { PI } else { 0.0 }.copysign(y);
+    }
+    if y.abs() > x.abs() {
+        // Nearer the y axis, so measure from there.
+        return FRAC_PI_2.copysign(y) - atan_kernel(x / y);
+    }
+    let angle = atan_kernel(y / x);
+    if x > 0.0 {
+        angle
+    } else if y.is_sign_negative() {
+        angle - PI
+    } else {
+        angle + PI
+    }
+}
@@ -0,0 +1,168 @@
+// Stand-in for `src/wasm4.rs`: the sound constants, with `tone` recorded instead of played.
+// Input and drawing are only here so the game's modules compile; input reads nothing and
+// drawing does nothing.
+
+use std::cell::{Cell, RefCell};
+
+pub const BUTTON_1: u8 = 1;
+pub const BUTTON_2: u8 = 2;
+pub const BUTTON_LEFT: u8 = 16;
+pub const BUTTON_RIGHT: u8 = 32;
+pub const BUTTON_UP: u8 = 64;
+pub const BUTTON_DOWN: u8 = 128;
+
+pub const MOUSE_LEFT: u8 = 1;
+pub const MOUSE_RIGHT: u8 = 2;
+pub const MOUSE_MIDDLE: u8 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawColors(u16);
+
+impl DrawColors {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub const fn first(self, color: u8) -> Self {
+        Self(self.0 & !0xf | color as u16 & 0xf)
+    }
+
+    pub const fn second(self, color: u8) -> Self {
+        Self(self.0 & !0xf0 | (color as u16 & 0xf) << 4)
+    }
+
+    pub const fn first_color(self) -> u8 {
+        (self.0 & 0xf) as u8
+    }
+
+    pub fn set(self) {}
+}
+
+pub fn set_palette(_colors: [u32; 4]) {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Gamepad {
+    held: u8,
+    last: u8,
+}
+
+impl Gamepad {
+    pub const fn new() -> Self {
+        Self {
+            held: u8::MAX,
+            last: u8::MAX,
+        }
+    }
+
+    pub const fn read(_player: usize) -> u8 {
+        0
+    }
+
+    pub const fn update(&mut self, buttons: u8) {
+        self.last = self.held;
+        self.held = buttons;
+    }
+
+    pub const fn held(self) -> u8 {
+        self.held
+    }
+
+    pub const fn pressed(self) -> u8 {
+        self.held & !self.last
+    }
+
+    pub const fn released(self) -> u8 {
+        self.last & !self.held
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Mouse {
+    pub x: i16,
+    pub y: i16,
+    pub buttons: u8,
+}
+
+impl Mouse {
+    pub const fn read() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            buttons: 0,
+        }
+    }
+}
+
+pub struct Framebuffer;
+
+impl Framebuffer {
+    /// Always one to be had, as there is no screen to share.
+    pub const fn take() -> Option<Self> {
+        Some(Self)
+    }
+
+    pub fn pixel(&self, _x: u32, _y: u32) -> u8 {
+        0
+    }
+
+    pub fn set_pixel(&mut self, _x: u32, _y: u32, _color: u8) {}
+}
+
+pub fn line(_x1: i32, _y1: i32, _x2: i32, _y2: i32) {}
+
+pub fn oval(_x: i32, _y: i32, _width: u32, _height: u32) {}
+
+pub fn rect(_x: i32, _y: i32, _width: u32, _height: u32) {}
+
+pub fn text<T: AsRef<str>>(_text: T, _x: i32, _y: i32) {}
+
+pub const TONE_PULSE1: u32 = 0;
+pub const TONE_PULSE2: u32 = 1;
+pub const TONE_TRIANGLE: u32 = 2;
+pub const TONE_NOISE: u32 = 3;
+pub const TONE_MODE1: u32 = 0;
+pub const TONE_MODE2: u32 = 4;
+pub const TONE_MODE3: u32 = 8;
+pub const TONE_MODE4: u32 = 12;
+
+/// One `tone` call and the frame it was made on.
+#[derive(Clone, Copy)]
+pub struct Tone {
+    pub frame: u32,
+    pub frequency: u32,
+    pub duration: u32,
+    pub volume: u32,
+    pub flags: u32,
+}
+
+thread_local! {
+    static FRAME: Cell<u32> = const { Cell::new(0) };
+    static TONES: RefCell<Vec<Tone>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Stamps the following `tone` calls with `frame`.
+pub fn set_frame(frame: u32) {
+    FRAME.with(|current| current.set(frame));
+}
+
+/// Everything recorded so far, oldest first.
+pub fn take_tones() -> Vec<Tone> {
+    TONES.with(|tones| tones.take())
+}
+
+pub fn tone(frequency: u32, duration: u32, volume: u32, flags: u32) {
+    let frame = FRAME.with(Cell::get);
+    TONES.with(|tones| {
+        tones.borrow_mut().push(Tone {
+            frame,
+            frequency,
+            duration,
+            volume,
+            flags,
+        });
+    });
+}
+
+pub fn trace<T: AsRef<str>>(text: T) {
+    eprintln!("{}", text.as_ref());
+}
@@ -0,0 +1,93 @@
+// Air around planets: how it slows whatever flies through it, and how it heats ships.
+
+use space_delivery_tools::geometry::Vec2;
+use space_delivery_tools::planet::Planet;
+use space_delivery_tools::player::Player;
+use space_delivery_tools::scalar::{scalar, to_f32};
+
+const RADIUS: f32 = 40.0;
+
+// The first planet from seed 0 on with air and nothing to land on, which makes it a gas giant.
+fn gas_giant() -> Planet {
+    (0..)
+        .map(|seed| Planet::new(Vec2::ZERO, scalar(RADIUS), 100, seed))
+        .find(|planet| !planet.takes_deliveries() && planet.drag(surface(planet, 0.0)).is_some())
+        .expect("some seed makes a gas giant")
+}
+
+// The point `height` radii above the planet's surface.
+fn surface(planet: &Planet, height: f32) -> Vec2 {
+    planet.pos + Vec2::new(planet.radius * (1.0 + height), scalar(0.0))
+}
+
+#[test]
+fn drag_is_the_planets_air_at_the_surface() {
+    let planet = gas_giant();
+    let air = to_f32(scalar(0.05));
+    let drag = planet.drag(surface(&planet, 0.0)).map_or(0.0, to_f32);
+    // `fast-math` distances are a few percent off.
+    assert!((drag - air).abs() < air * 0.1, "{drag} against {air}");
+    // Down in the planet it is no thicker.
+    assert_eq!(planet.drag(surface(&planet, -0.5)).map(to_f32), Some(air));
+}
+
+#[test]
+fn drag_thins_out_with_height() {
+    let planet = gas_giant();
+    let drags: Vec<f32> = [0.0, 0.1, 0.2, 0.3, 0.4, 0.49]
+        .into_iter()
+        .map(|height| planet.drag(surface(&planet, height)).map_or(0.0, to_f32))
+        .collect();
+    assert!(drags.windows(2).all(|pair| pair[1] < pair[0]), "{drags:?}");
+    assert!(drags[5] > 0.0);
+}
+
+#[test]
+fn drag_is_zero_from_the_top_of_the_air() {
+    let planet = gas_giant();
+    assert!(planet.drag(surface(&planet, 0.5)).is_none());
+    assert!(planet.drag(surface(&planet, 2.0)).is_none());
+}
+
+#[test]
+fn planets_without_air_have_no_drag() {
+    let rocky = (0..)
+        .map(|seed| Planet::new(Vec2::ZERO, scalar(RADIUS), 100, seed))
+        .find(|planet| planet.takes_deliveries() && planet.drag(surface(planet, 0.0)).is_none())
+        .expect("some seed makes a rocky planet");
+    assert!(rocky.drag(surface(&rocky, 0.1)).is_none());
+}
+
+// Whether a ship held at `speed` in air `drag` thick starts scorching within ten seconds.
+fn scorches(speed: f32, drag: f32) -> bool {
+    let mut player = Player::new(Vec2::ZERO);
+    (0..600).any(|_| {
+        player.physics.vel = Vec2::from_f32(speed, 0.0);
+        player.heat_up(scalar(drag));
+        player.update();
+        player.is_scorching()
+    })
+}
+
+#[test]
+fn only_fast_ships_scorch() {
+    assert!(!scorches(0.2, 0.05));
+    assert!(!scorches(1.0, 0.002));
+    assert!(scorches(1.5, 0.05));
+    assert!(scorches(2.0, 0.01));
+}
+
+#[test]
+fn heat_stays_within_one_and_cools_off() {
+    let mut player = Player::new(Vec2::ZERO);
+    for _ in 0..100 {
+        player.physics.vel = Vec2::from_f32(2.0, 0.0);
+        player.heat_up(scalar(0.05));
+    }
+    assert_eq!(to_f32(player.heat()), 1.0);
+    for _ in 0..150 {
+        player.update();
+    }
+    assert_eq!(to_f32(player.heat()), 0.0);
+    assert!(!player.is_scorching());
+}
@@ -0,0 +1,206 @@
+// `Fixed` against `f64` over its whole range, edges included.
+
+use space_delivery_tools::fixed::Fixed;
+
+const ULP: f64 = 1.0 / 65_536.0;
+
+fn raw(value: Fixed) -> i32 {
+    value.to_bits().cast_signed()
+}
+
+fn real(value: Fixed) -> f64 {
+    f64::from(raw(value)) * ULP
+}
+
+// The nearest values to the edges and zero, and a spread across everything in between.
+fn samples(step: usize) -> Vec<Fixed> {
+    let edges = [
+        i32::MIN,
+        i32::MIN + 1,
+        -65_537,
+        -65_536,
+        -1,
+        0,
+        1,
+        65_536,
+        65_537,
+    ];
+    let edges = edges.into_iter().chain([i32::MAX - 1, i32::MAX]);
+    edges
+        .chain((i32::MIN..=i32::MAX).step_by(step))
+        .map(Fixed::from_raw)
+        .collect()
+}
+
+// What an exact `value` should come to: rounded toward zero and saturated.
+fn truncated(value: f64) -> f64 {
+    (value / ULP)
+        .trunc()
+        .clamp(f64::from(i32::MIN), f64::from(i32::MAX))
+}
+
+// `f64` itself rounds the products of the biggest values, so allow a last bit either way.
+fn assert_within_ulp(got: Fixed, expected: f64, what: impl Fn() -> String) {
+    let error = (f64::from(raw(got)) - expected).abs();
+    assert!(
+        error <= 1.0,
+        "{} is {}, not {}",
+        what(),
+        real(got),
+        expected * ULP
+    );
+}
+
+#[test]
+fn conversions_saturate() {
+    assert_eq!(raw(Fixed::from_f32(1e9)), i32::MAX);
+    assert_eq!(raw(Fixed::from_f32(-1e9)), i32::MIN);
+    assert_eq!(raw(Fixed::from_f32(f32::INFINITY)), i32::MAX);
+    assert_eq!(raw(Fixed::from_f32(f32::NEG_INFINITY)), i32::MIN);
+    assert_eq!(raw(Fixed::from_f32(f32::NAN)), 0);
+    assert_eq!(raw(Fixed::from(40_000)), i32::MAX);
+    assert_eq!(raw(Fixed::from(-40_000)), i32::MIN);
+    assert_eq!(raw(Fixed::from(-32_768)), i32::MIN);
+    assert_eq!(raw(-Fixed::MIN), i32::MAX);
+    assert_eq!(raw(Fixed::MIN.abs()), i32::MAX);
+}
+
+#[test]
+fn conversions_round_to_nearest() {
+    assert_eq!(raw(Fixed::from_f32(1.5 / 65_536.0)), 2);
+    assert_eq!(raw(Fixed::from_f32(-1.5 / 65_536.0)), -2);
+    assert_eq!(raw(Fixed::from_f32(1.25 / 65_536.0)), 1);
+    for value in samples(1_000_003) {
+        let back = Fixed::from_f32(value.to_f32());
+        // `f32` holds 24 bits, so values past 256 come back to the nearest it can hold.
+        let slack = (real(value).abs() / 256.0).max(1.0);
+        assert!(
+            f64::from(raw(back) - raw(value)).abs() <= slack,
+            "{} came back as {}",
+            real(value),
+            real(back)
+        );
+    }
+}
+
+#[test]
+fn sums_saturate() {
+    let values = samples(10_000_019);
+    for &a in &values {
+        for &b in &values {
+            let exact = |value: f64| truncated(value);
+            assert_eq!(f64::from(raw(a + b)), exact(real(a) + real(b)));
+            assert_eq!(f64::from(raw(a - b)), exact(real(a) - real(b)));
+        }
+    }
+}
+
+#[test]
+fn products_round_toward_zero_and_saturate() {
+    let values = samples(10_000_019);
+    for &a in &values {
+        for &b in &values {
+            assert_within_ulp(a * b, truncated(real(a) * real(b)), || {
+                format!("{} * {}", real(a), real(b))
+            });
+        }
+    }
+    assert_eq!(raw(Fixed::MAX * Fixed::MAX), i32::MAX);
+    assert_eq!(raw(Fixed::MIN * Fixed::MAX), i32::MIN);
+    assert_eq!(raw(Fixed::MIN * Fixed::MIN), i32::MAX);
+}
+
+#[test]
+fn quotients_round_toward_zero_and_saturate() {
+    let values = samples(10_000_019);
+    for &a in &values {
+        for &b in values.iter().filter(|b| raw(**b) != 0) {
+            assert_within_ulp(a / b, truncated(real(a) / real(b)), || {
+                format!("{} / {}", real(a), real(b))
+            });
+        }
+    }
+}
+
+// `MIN` is a bit further from zero than `MAX`, so leave out results that saturate.
+#[test]
+fn signs_do_not_change_sizes() {
+    let saturated = |value: Fixed| value == Fixed::MAX || value == Fixed::MIN;
+    let values = samples(10_000_019);
+    let values: Vec<_> = values.into_iter().filter(|v| *v != Fixed::MIN).collect();
+    for &a in &values {
+        for &b in &values {
+            if !saturated(a * b) {
+                assert!(raw((-a) * b) == raw(-(a * b)), "-{} * {}", real(a), real(b));
+            }
+            if b != Fixed::ZERO && !saturated(a / b) {
+                assert!(raw((-a) / b) == raw(-(a / b)), "-{} / {}", real(a), real(b));
+                assert!(raw(a / (-b)) == raw(-(a / b)), "{} / -{}", real(a), real(b));
+            }
+        }
+    }
+    assert_eq!(raw(Fixed::from(-1) / 3), -21_845);
+    assert_eq!(raw(Fixed::from(1) / 3), 21_845);
+}
+
+#[test]
+fn dividing_by_zero_saturates() {
+    assert_eq!(raw(Fixed::from(5) / Fixed::ZERO), i32::MAX);
+    assert_eq!(raw(Fixed::from(-5) / Fixed::ZERO), i32::MIN);
+    assert_eq!(raw(Fixed::ZERO / Fixed::ZERO), 0);
+    assert_eq!(raw(Fixed::MIN / Fixed::from_raw(-1)), i32::MAX);
+}
+
+#[test]
+fn square_roots() {
+    for value in samples(1_000_003) {
+        let expected = if raw(value) < 0 {
+            0.0
+        } else {
+            truncated(real(value).sqrt())
+        };
+        assert_within_ulp(value.sqrt(), expected, || format!("√{}", real(value)));
+    }
+    assert_eq!(raw(Fixed::MIN.sqrt()), 0);
+    assert_eq!(raw(Fixed::from(4).sqrt()), 2 << 16);
+}
+
+#[test]
+fn lengths_do_not_overflow() {
+    let values = samples(10_000_019);
+    for &x in &values {
+        for &y in &values {
+            let expected = truncated(real(x).hypot(real(y)));
+            assert_within_ulp(Fixed::hypot(x, y), expected, || {
+                format!("hypot({}, {})", real(x), real(y))
+            });
+        }
+    }
+    assert_eq!(raw(Fixed::hypot(Fixed::MAX, Fixed::MAX)), i32::MAX);
+    assert_eq!(raw(Fixed::hypot(Fixed::MIN, Fixed::MIN)), i32::MAX);
+    assert_eq!(raw(Fixed::hypot(Fixed::from(3), Fixed::from(-4))), 5 << 16);
+}
+
+// Worst error of `f` against `expected` over the whole range.
+fn worst_error(f: impl Fn(Fixed) -> Fixed, expected: impl Fn(f64) -> f64) -> (f64, f64) {
+    samples(100_003)
+        .into_iter()
+        .map(|value| ((real(f(value)) - expected(real(value))).abs(), real(value)))
+        .fold(
+            (0.0, 0.0),
+            |worst, next| if next.0 > worst.0 { next } else { worst },
+        )
+}
+
+#[test]
+fn sines_and_cosines() {
+    // Two bits from the kernels' own rounding, however many turns the angle is.
+    let bound = 3.0 * ULP;
+    for (name, error) in [
+        ("sin", worst_error(Fixed::sin, f64::sin)),
+        ("cos", worst_error(Fixed::cos, f64::cos)),
+    ] {
+        let (worst, at) = error;
+        assert!(worst <= bound, "{name} is off by {worst:e} at {at}");
+    }
+}
@@ -0,0 +1,140 @@
+// Intersection and ray casting, which collisions, culling and the edge markers rely on.
+
+use space_delivery_tools::geometry::{Circle, Ray, Rect, Segment, Vec2};
+use space_delivery_tools::scalar::{scalar, to_f32};
+
+fn at(x: f32, y: f32) -> Vec2 {
+    Vec2::from_f32(x, y)
+}
+
+fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+    Rect::new(at(x, y), at(width, height))
+}
+
+fn circle(x: f32, y: f32, radius: f32) -> Circle {
+    Circle::new(at(x, y), scalar(radius))
+}
+
+fn corners(rect: &Rect) -> [f32; 4] {
+    let bottom_right = rect.bottom_right();
+    [
+        rect.top_left.x,
+        rect.top_left.y,
+        bottom_right.x,
+        bottom_right.y,
+    ]
+    .map(to_f32)
+}
+
+// `fast-math` lengths are up to 6% off, and fixed point drops bits past the sixteenth.
+fn assert_near(value: f32, expected: f32) {
+    let within = 0.07 * expected.abs().max(1.0);
+    assert!(
+        (value - expected).abs() < within,
+        "{value} against {expected}"
+    );
+}
+
+fn assert_cast(cast: Option<(f32, f32)>, (into, out): (f32, f32)) {
+    let cast = cast.expect("it goes through");
+    assert_near(cast.0, into);
+    assert_near(cast.1, out);
+}
+
+#[test]
+fn rectangles_overlap_where_both_cover() {
+    let a = rect(0.0, 0.0, 10.0, 10.0);
+    let overlap = a
+        .intersection(&rect(5.0, -5.0, 10.0, 10.0))
+        .map(|r| corners(&r));
+    assert_eq!(overlap, Some([5.0, 0.0, 10.0, 5.0]));
+    assert!(a.intersects(&rect(2.0, 2.0, 2.0, 2.0)));
+    // The bottom and right edges are outside, so rectangles that only touch do not meet.
+    assert!(!a.intersects(&rect(10.0, 0.0, 5.0, 5.0)));
+    assert!(!a.intersects(&rect(0.0, 10.0, 5.0, 5.0)));
+    assert!(!a.intersects(&rect(-20.0, -20.0, 5.0, 5.0)));
+    assert_eq!(
+        corners(&a.union(&rect(-5.0, 20.0, 1.0, 1.0))),
+        [-5.0, 0.0, 10.0, 21.0]
+    );
+}
+
+#[test]
+fn rectangles_meet_circles_at_the_nearest_point() {
+    let a = rect(0.0, 0.0, 10.0, 10.0);
+    assert!(a.intersects_circle(&circle(5.0, 5.0, 1.0)));
+    assert!(a.intersects_circle(&circle(14.0, 5.0, 5.0)));
+    // Past the corner the gap is diagonal: √(3² + 3²) is more than 4.
+    assert!(!a.intersects_circle(&circle(13.0, 13.0, 4.0)));
+    assert!(a.intersects_circle(&circle(13.0, 13.0, 4.5)));
+    assert_eq!(
+        corners(&circle(5.0, -5.0, 2.0).bounds()),
+        [3.0, -7.0, 7.0, -3.0]
+    );
+}
+
+#[test]
+fn segments_catch_what_points_skip() {
+    let planet = circle(0.0, 0.0, 5.0);
+    // A ship fast enough to be either side of the planet on two frames running.
+    let path = Segment::new(at(-20.0, 1.0), at(20.0, 1.0));
+    assert!(!planet.contains(path.start) && !planet.contains(path.end));
+    assert!(path.intersects_circle(&planet));
+    assert!(!Segment::new(at(-20.0, 6.0), at(20.0, 6.0)).intersects_circle(&planet));
+    // Heading at it but stopping short, and heading away.
+    assert!(!Segment::new(at(-20.0, 0.0), at(-6.0, 0.0)).intersects_circle(&planet));
+    assert!(!Segment::new(at(6.0, 0.0), at(20.0, 0.0)).intersects_circle(&planet));
+    // Standing still is just a point.
+    assert!(Segment::new(at(3.0, 0.0), at(3.0, 0.0)).intersects_circle(&planet));
+}
+
+#[test]
+fn the_closest_point_on_a_segment_stays_on_it() {
+    let segment = Segment::new(at(0.0, 0.0), at(10.0, 0.0));
+    let closest = |x, y| {
+        let point = segment.closest_point(at(x, y));
+        (to_f32(point.x), to_f32(point.y))
+    };
+    let (x, y) = closest(4.0, 7.0);
+    assert_near(x, 4.0);
+    assert_eq!(y, 0.0);
+    assert_eq!(closest(-3.0, 2.0), (0.0, 0.0));
+    assert_eq!(closest(15.0, -2.0), (10.0, 0.0));
+}
+
+#[test]
+fn rays_cast_into_rectangles() {
+    let screen = rect(0.0, 0.0, 160.0, 160.0);
+    let cast = |from: Vec2, towards: Vec2| {
+        Ray::new(from, towards)
+            .and_then(|ray| ray.cast_rect(&screen))
+            .map(|(into, out)| (to_f32(into), to_f32(out)))
+    };
+    // From the middle, out through the right edge.
+    assert_cast(cast(at(80.0, 80.0), at(1.0, 0.0)), (0.0, 80.0));
+    // From outside, in through the top and out through the bottom.
+    assert_cast(cast(at(80.0, -20.0), at(0.0, 3.0)), (20.0, 180.0));
+    // Corner to corner.
+    let diagonal = 2.0_f32.sqrt();
+    assert_cast(
+        cast(at(-10.0, -10.0), at(1.0, 1.0)),
+        (10.0 * diagonal, 170.0 * diagonal),
+    );
+    // Alongside, and facing away.
+    assert_eq!(cast(at(-10.0, -10.0), at(1.0, 0.0)), None);
+    assert_eq!(cast(at(200.0, 80.0), at(1.0, 0.0)), None);
+}
+
+#[test]
+fn rays_need_a_direction() {
+    assert!(Ray::new(at(1.0, 1.0), Vec2::ZERO).is_none());
+    let ray = Ray::new(at(1.0, 1.0), at(0.0, -4.0)).expect("it points up");
+    assert_near(to_f32(ray.direction().y), -1.0);
+    assert_near(to_f32(ray.at(scalar(3.0)).y), -2.0);
+}
+
+#[test]
+fn a_quarter_turn_is_exact() {
+    let turned = at(3.0, -2.0).perpendicular();
+    assert_eq!((to_f32(turned.x), to_f32(turned.y)), (2.0, 3.0));
+}
@@ -0,0 +1,190 @@
+// The spatial grid against looking at everything, the way the world used to.
+
+use heapless::HistoryBuffer;
+use space_delivery_tools::geometry::{Rect, Vec2};
+use space_delivery_tools::grid::{Grid, CAPACITY};
+use space_delivery_tools::ring::Ring;
+use space_delivery_tools::scalar::scalar;
+
+// Cells are 128 pixels across and repeat every 16 of them.
+const REPEAT: f32 = 128.0 * 16.0;
+
+fn at(x: f32, y: f32) -> Vec2 {
+    Vec2::from_f32(x, y)
+}
+
+fn square(x: f32, y: f32, size: f32) -> Rect {
+    Rect::new(at(x, y), at(size, size))
+}
+
+fn grid(positions: &[Vec2]) -> Grid {
+    let mut grid = Grid::new();
+    for &pos in positions {
+        grid.insert(pos);
+    }
+    grid
+}
+
+fn near(grid: &Grid, area: &Rect) -> Vec<usize> {
+    grid.near(area).collect()
+}
+
+#[test]
+fn finds_what_is_in_the_area() {
+    let grid = grid(&[at(10.0, 10.0), at(500.0, 500.0), at(20.0, 30.0)]);
+    assert_eq!(near(&grid, &square(0.0, 0.0, 40.0)), [0, 2]);
+    assert_eq!(near(&grid, &square(480.0, 480.0, 40.0)), [1]);
+}
+
+#[test]
+fn tells_negative_cells_from_positive_ones() {
+    let grid = grid(&[at(-64.0, -64.0), at(64.0, 64.0), at(-200.0, 64.0)]);
+    assert_eq!(near(&grid, &square(60.0, 60.0, 8.0)), [1]);
+    assert_eq!(near(&grid, &square(-68.0, -68.0, 8.0)), [0]);
+    assert_eq!(near(&grid, &square(-204.0, 60.0, 8.0)), [2]);
+}
+
+#[test]
+fn shares_buckets_a_repeat_apart() {
+    let grid = grid(&[
+        at(10.0, 10.0),
+        at(10.0 + REPEAT, 10.0),
+        at(10.0 - 3.0 * REPEAT, 10.0 + REPEAT),
+    ]);
+    assert_eq!(near(&grid, &square(0.0, 0.0, 20.0)), [0, 1, 2]);
+    // Callers check the distance, so the far ones never win.
+    let nearest = grid.nearest(&[0, 1, 2], at(0.0, 0.0), scalar(100.0), |&index| {
+        [at(10.0, 10.0), at(10.0 + REPEAT, 10.0)]
+            .get(index)
+            .copied()
+    });
+    assert_eq!(nearest, Some(&0));
+}
+
+#[test]
+fn covers_every_cell_an_area_straddles() {
+    let corners = [
+        at(127.0, 127.0),
+        at(129.0, 127.0),
+        at(127.0, 129.0),
+        at(129.0, 129.0),
+    ];
+    let grid = grid(&corners);
+    assert_eq!(near(&grid, &square(120.0, 120.0, 16.0)), [0, 1, 2, 3]);
+    // The same across the origin, where the cells go negative.
+    let corners = corners.map(|corner| corner - at(128.0, 128.0));
+    let grid = self::grid(&corners);
+    assert_eq!(near(&grid, &square(-8.0, -8.0, 16.0)), [0, 1, 2, 3]);
+}
+
+#[test]
+fn nearest_passes_over_what_is_out_of_range_or_left_out() {
+    let positions = [at(0.0, 50.0), at(0.0, 20.0), at(0.0, 10.0), at(0.0, 300.0)];
+    let grid = grid(&positions);
+    let indices = [0, 1, 2, 3];
+    let position = |&index: &usize| positions.get(index).copied();
+    let nearest = |pos, range| grid.nearest(&indices, pos, scalar(range), position);
+    assert_eq!(nearest(at(0.0, 0.0), 100.0), Some(&2));
+    assert_eq!(nearest(at(0.0, 45.0), 100.0), Some(&0));
+    assert_eq!(nearest(at(0.0, 200.0), 50.0), None);
+    let odd = grid.nearest(&indices, at(0.0, 0.0), scalar(100.0), |&index: &usize| {
+        position(&index).filter(|_| index % 2 == 1)
+    });
+    assert_eq!(odd, Some(&1));
+}
+
+// What `grid` finds of `positions` in `area` is just what is there.
+fn assert_finds_exactly(grid: &Grid, positions: &[Vec2], area: &Rect, when: &str) {
+    let inside = |&(_, &pos): &(usize, &Vec2)| area.contains(pos);
+    let found: Vec<usize> = grid
+        .near(area)
+        .filter_map(|index| positions.get(index).map(|pos| (index, pos)))
+        .filter(inside)
+        .map(|(index, _)| index)
+        .collect();
+    let expected: Vec<usize> = positions
+        .iter()
+        .enumerate()
+        .filter(inside)
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(found, expected, "{when}");
+}
+
+#[test]
+fn keeps_in_step_with_a_history_buffer() {
+    let mut random = oorandom::Rand32::new(7);
+    let mut coordinate = || (random.rand_float() - 0.5) * 3.0 * REPEAT;
+    let mut grid = Grid::new();
+    let mut positions: HistoryBuffer<Vec2, CAPACITY> = HistoryBuffer::new();
+    for written in 0..CAPACITY * 3 {
+        let pos = at(coordinate(), coordinate());
+        grid.insert(pos);
+        positions.write(pos);
+        if written % 50 != 0 {
+            continue;
+        }
+        for _ in 0..20 {
+            let area = square(coordinate(), coordinate(), 300.0);
+            let when = format!("after {written} writes");
+            assert_finds_exactly(&grid, positions.as_slice(), &area, &when);
+        }
+    }
+}
+
+#[test]
+fn rings_number_like_history_buffers() {
+    let mut ring: Ring<u32, CAPACITY> = Ring::new();
+    let mut history: HistoryBuffer<u32, CAPACITY> = HistoryBuffer::new();
+    for written in 0..u32::try_from(CAPACITY * 3 + 7).unwrap() {
+        ring.write(written);
+        history.write(written);
+        assert_eq!(ring.as_slice(), history.as_slice());
+    }
+    ring.clear();
+    ring.write(1);
+    assert_eq!(ring.as_slice(), [1]);
+}
+
+#[test]
+fn follows_everything_moved_back() {
+    let mut random = oorandom::Rand32::new(7);
+    let mut coordinate = || (random.rand_float() - 0.5) * 3.0 * REPEAT;
+    let mut grid = Grid::new();
+    let mut positions: Ring<Vec2, CAPACITY> = Ring::new();
+    for written in 0..CAPACITY + 40 {
+        let pos = at(coordinate(), coordinate());
+        grid.insert(pos);
+        positions.write(pos);
+        // Not whole repeats, so everything changes bucket.
+        if written % 100 == 99 {
+            for pos in positions.as_mut_slice() {
+                *pos += at(-1_000.0, 3_000.0);
+            }
+            grid.relink(positions.as_slice(), |&pos| pos);
+            for _ in 0..20 {
+                let area = square(coordinate() - 1_000.0, coordinate() + 3_000.0, 300.0);
+                let when = format!("after moving {written} back");
+                assert_finds_exactly(&grid, positions.as_slice(), &area, &when);
+            }
+        }
+    }
+    // Writes go on taking the place of the oldest.
+    grid.insert(at(5.0, 5.0));
+    positions.write(at(5.0, 5.0));
+    assert_finds_exactly(
+        &grid,
+        positions.as_slice(),
+        &square(0.0, 0.0, 10.0),
+        "after",
+    );
+}
+
+#[test]
+fn starts_over_when_cleared() {
+    let mut grid = grid(&[at(10.0, 10.0), at(20.0, 20.0)]);
+    grid.clear();
+    assert_eq!(near(&grid, &square(0.0, 0.0, 40.0)), []);
+    grid.insert(at(30.0, 30.0));
+    assert_eq!(near(&grid, &square(0.0, 0.0, 40.0)), [0]);
+}
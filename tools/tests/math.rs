@@ -0,0 +1,57 @@
+// The game's `math` against `libm`, within the bounds for the accurate or, built with
+// `fast-math`, the fast versions.
+
+use space_delivery_tools::matherr::{measure, CHECKS};
+
+// Every check of the function `name`, over all of their ranges.
+fn assert_within_bounds(name: &str) {
+    let checks: Vec<_> = CHECKS
+        .iter()
+        .filter(|check| check.name.split('(').next() == Some(name))
+        .collect();
+    assert!(!checks.is_empty(), "no checks for {name}");
+    for check in checks {
+        let (worst, at) = measure(check);
+        assert!(
+            worst <= check.bound(),
+            "{} is off by {worst:e} at {at}, more than {:e}",
+            check.name,
+            check.bound()
+        );
+    }
+}
+
+#[test]
+fn sqrt() {
+    assert_within_bounds("sqrt");
+}
+
+#[test]
+fn sin() {
+    assert_within_bounds("sin");
+}
+
+#[test]
+fn cos() {
+    assert_within_bounds("cos");
+}
+
+#[test]
+fn atan2() {
+    assert_within_bounds("atan2");
+}
+
+#[test]
+fn exp() {
+    assert_within_bounds("exp");
+}
+
+#[test]
+fn powf() {
+    assert_within_bounds("powf");
+}
+
+#[test]
+fn floor() {
+    assert_within_bounds("floor");
+}
@@ -0,0 +1,89 @@
+// The MIDI converter on `tests/midi/channels.mid`, a one-bar file at 96 MIDI ticks a quarter
+// (8 to a game tick) in 24/4, so the bar is 288 game ticks. Its tracks, in order:
+//
+// - "Conductor": 120 bpm and the time signature, no notes.
+// - "Lead", channel 1: C4 from 3 to 45, E4 and G4 together from 48 to 96, a key 0 note, and D4
+//   from 200 to 203.
+// - "Triangle bass": C2 from 0 to 384.
+// - "Drums", channel 10: D2 from 0 to 8.
+// - "Harmony", channel 2: 17 notes from C5 up, 16 ticks each, one after the other.
+// - "Extra", channel 3: one note, with no channel left for it.
+
+use space_delivery_tools::midi::{self, Conversion};
+use space_delivery_tools::songc;
+use std::fs;
+
+fn convert(name: &str) -> Conversion {
+    let path = format!("{}/tests/midi/{name}.mid", env!("CARGO_MANIFEST_DIR"));
+    let data = fs::read(&path).unwrap_or_else(|error| panic!("{path}: {error}"));
+    midi::convert(&data).unwrap_or_else(|error| panic!("{path}: {error}"))
+}
+
+// The notes of the pattern called `name`.
+fn pattern<'a>(source: &'a str, name: &str) -> &'a str {
+    let mut lines = source.lines();
+    lines.find(|line| *line == format!("pattern {name}"));
+    lines
+        .next()
+        .unwrap_or_else(|| panic!("no pattern {name}"))
+        .trim()
+}
+
+#[test]
+fn assigns_tracks_to_channels() {
+    let source = convert("channels").source;
+    // Named tracks and drums first, then the rest in file order; "Extra" finds no room.
+    let order = source.lines().skip_while(|line| *line != "order").nth(1);
+    assert_eq!(order, Some("  pulse1_0 pulse2_1 triangle_2 noise_3"));
+    assert!(pattern(&source, "triangle_2").starts_with("C2/"));
+    assert!(pattern(&source, "noise_3").starts_with("D2/"));
+}
+
+#[test]
+fn quantises_to_ticks() {
+    let source = convert("channels").source;
+    // 3 to 45 rounds to 0 to 6, and 200 to 203 to a tick of its own. Of the chord the highest
+    // note is kept, and key 0 leaves a rest.
+    assert_eq!(
+        pattern(&source, "pulse1_0"),
+        "C4/t6 G4/t6 -/t13 D4/t1 -/t255 -/t7"
+    );
+    // Rests fill out the bar, split where they are longer than 255 ticks.
+    assert_eq!(pattern(&source, "triangle_2"), "C2/t48 -/t240");
+    assert_eq!(pattern(&source, "noise_3"), "D2/t1 -/t255 -/t32");
+    assert!(source.contains("\ntempo 120\n"));
+}
+
+#[test]
+fn drops_what_a_pattern_cannot_hold() {
+    let source = convert("channels").source;
+    // 17 notes and the rest make 18 items. The rest taking the place of the dropped notes is
+    // too long for one item, so three notes go to make room for two.
+    assert_eq!(
+        pattern(&source, "pulse2_1"),
+        "C5/t2 C#5/t2 D5/t2 D#5/t2 E5/t2 F5/t2 F#5/t2 G5/t2 G#5/t2 A5/t2 A#5/t2 B5/t2 C6/t2 \
+         C#6/t2 -/t255 -/t5"
+    );
+}
+
+#[test]
+fn warns_about_what_did_not_convert() {
+    assert_eq!(
+        convert("channels").warnings,
+        [
+            "track `Extra` dropped with 1 notes, every channel is taken",
+            "pulse1: 1 notes dropped from chords, channels play one note at a time",
+            "pulse1: 1 notes lengthened to one tick",
+            "pulse1: 1 notes dropped for being below C#-1",
+            "pulse2: 3 notes dropped from bar 1, patterns hold 16 notes",
+        ]
+    );
+}
+
+#[test]
+fn compiles() {
+    let source = convert("channels").source;
+    if let Err(error) = songc::compile(&source) {
+        panic!("line {}: {}", error.line, error.message);
+    }
+}
@@ -0,0 +1,61 @@
+// Recordings from each header version the game has written, played back through the world.
+// Any change to the simulation or to what the checksum covers moves these; record why in the
+// commit when updating them. Replays only match on a build with the same math, so each build has
+// its own expectations.
+
+use space_delivery_tools::input::Playback;
+use space_delivery_tools::{replay, rerun};
+use std::fs;
+
+// File in `tests/replays`, frames played and checksum at the end.
+#[cfg(not(any(feature = "fixed-point", feature = "fast-math")))]
+const EXPECTED: [(&str, u32, u32); 3] = [
+    ("solo-v2", 1417, 0xfed9_73c8),
+    ("lineup-v3", 10200, 0xe18e_068a),
+    ("upgrades-v4", 15300, 0x43b4_f8fb),
+];
+#[cfg(all(feature = "fast-math", not(feature = "fixed-point")))]
+const EXPECTED: [(&str, u32, u32); 3] = [
+    ("solo-v2", 1407, 0x8761_e537),
+    ("lineup-v3", 10200, 0x9599_c141),
+    ("upgrades-v4", 15300, 0x2caa_cb33),
+];
+// Fixed point has no fast math of its own.
+#[cfg(feature = "fixed-point")]
+const EXPECTED: [(&str, u32, u32); 3] = [
+    ("solo-v2", 1382, 0x029c_d479),
+    ("lineup-v3", 10200, 0xb8c2_bc4c),
+    ("upgrades-v4", 15300, 0x53e1_b487),
+];
+
+fn assert_replays(name: &str) {
+    let path = format!("{}/tests/replays/{name}.sdr", env!("CARGO_MANIFEST_DIR"));
+    let contents = fs::read(&path).unwrap_or_else(|error| panic!("{path}: {error}"));
+    let replay = replay::read(&contents).unwrap_or_else(|error| panic!("{path}: {error}"));
+    let playback =
+        Playback::parse(&replay.data).unwrap_or_else(|error| panic!("{path}: {}", error.message()));
+    let rerun = rerun::rerun(playback, |_| {});
+    let Some(&(_, frames, checksum)) = EXPECTED.iter().find(|expected| expected.0 == name) else {
+        panic!("nothing expected of {name}");
+    };
+    assert_eq!(
+        (rerun.frames, format!("{:08x}", rerun.checksum)),
+        (frames, format!("{checksum:08x}")),
+        "{path}"
+    );
+}
+
+#[test]
+fn solo_from_version_2() {
+    assert_replays("solo-v2");
+}
+
+#[test]
+fn lineup_from_version_3() {
+    assert_replays("lineup-v3");
+}
+
+#[test]
+fn upgrades_from_version_4() {
+    assert_replays("upgrades-v4");
+}
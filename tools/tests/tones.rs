@@ -0,0 +1,41 @@
+// Music and sound effects played on the host, against tone logs `render --log` wrote when they
+// last sounded right. Rerender the log when a change to them is meant to be heard.
+
+use space_delivery_tools::sfx::Sound;
+use space_delivery_tools::wasm4::Tone;
+use space_delivery_tools::{apu, play};
+use std::fs;
+
+fn assert_sounds_like(tones: &[Tone], name: &str) {
+    let path = format!("{}/tests/tones/{name}.tones", env!("CARGO_MANIFEST_DIR"));
+    let golden = fs::read_to_string(&path).unwrap_or_else(|error| panic!("{path}: {error}"));
+    let mut log = Vec::new();
+    apu::write_log(&mut log, tones).unwrap();
+    let log = String::from_utf8(log).unwrap();
+    for (line, (played, expected)) in log.lines().zip(golden.lines()).enumerate() {
+        assert_eq!(played, expected, "{path}:{}", line + 1);
+    }
+    assert_eq!(log.lines().count(), golden.lines().count(), "{path}");
+}
+
+#[test]
+fn intro() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../music/intro.song");
+    let (tones, _) = play::song(play::load_song(path).unwrap(), None);
+    assert_sounds_like(&tones, "intro");
+}
+
+#[test]
+fn boost() {
+    assert_sounds_like(&play::sfx(Sound::Boost, 120), "boost");
+}
+
+#[test]
+fn pickup() {
+    assert_sounds_like(&play::sfx(Sound::Pickup, 120), "pickup");
+}
+
+#[test]
+fn delivery() {
+    assert_sounds_like(&play::sfx(Sound::Delivery, 120), "delivery");
+}
@@ -0,0 +1,64 @@
+// The world built and run on the host, the way the cart's `start` and `update` drive it.
+
+use space_delivery_tools::input::Input;
+use space_delivery_tools::mode::{Lineup, Mode};
+use space_delivery_tools::sfx::Sfx;
+use space_delivery_tools::wasm4;
+use space_delivery_tools::world::World;
+
+// Flies a solo run for `frames` frames, holding `gamepad`, and sums it up.
+fn fly(seed: u64, gamepad: u8, frames: u32) -> (u32, u32) {
+    let mut world = World::new();
+    let mut sfx = Sfx::new();
+    world.set_seed(seed);
+    world.start(Mode::Explore, Lineup::SOLO);
+    let input = Input {
+        gamepads: [gamepad, 0, 0, 0],
+        ..Input::default()
+    };
+    for _ in 0..frames {
+        world.update(input, &mut sfx);
+    }
+    drop(wasm4::take_tones());
+    (world.frame(), world.checksum())
+}
+
+#[test]
+fn runs_the_same_every_time() {
+    let run = fly(7, wasm4::BUTTON_UP, 600);
+    assert_eq!(run.0, 600);
+    assert_eq!(run, fly(7, wasm4::BUTTON_UP, 600));
+}
+
+#[test]
+fn follows_the_seed_and_the_input() {
+    let run = fly(7, wasm4::BUTTON_UP, 600);
+    assert_ne!(run, fly(8, wasm4::BUTTON_UP, 600));
+    assert_ne!(run, fly(7, wasm4::BUTTON_LEFT, 600));
+}
+
+// Fixed point only reaches about 32 sectors out, so a ship gets further only if the world
+// keeps moving back under it.
+#[test]
+fn flies_on_past_where_positions_run_out() {
+    let mut world = World::new();
+    let mut sfx = Sfx::new();
+    world.set_seed(7);
+    world.start(Mode::Zen, Lineup::SOLO);
+    // Boosting out of the wells on the way.
+    let input = Input {
+        gamepads: [wasm4::BUTTON_UP | wasm4::BUTTON_1, 0, 0, 0],
+        ..Input::default()
+    };
+    let mut last = world.sector();
+    for frame in 1..=40_000 {
+        world.update(input, &mut sfx);
+        drop(wasm4::take_tones());
+        if frame % 5_000 == 0 {
+            let sector = world.sector();
+            assert!(sector.1 < last.1, "stuck at {sector:?} by frame {frame}");
+            last = sector;
+        }
+    }
+    assert!(last.1 < -60, "only got to {last:?}");
+}